@@ -1,4 +1,4 @@
 #[tokio::main(flavor = "current_thread")]
-async fn main() -> color_eyre::Result<()> {
-    ryndns::cli::dyndns::main().await
+async fn main() {
+    ryndns::run(ryndns::cli::dyndns::main()).await;
 }
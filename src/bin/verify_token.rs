@@ -0,0 +1,4 @@
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    ryndns::run(ryndns::cli::verify_token::main()).await;
+}
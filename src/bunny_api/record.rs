@@ -5,18 +5,30 @@ use serde::{Deserialize, Serialize};
 
 use super::BUNNY_API_URL;
 
-#[derive(Deserialize, Debug)]
+/// A record as bunny.net stores it. Updates send the whole record back, so
+/// every attribute is kept, including those without a field here.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
 pub struct BunnyRecord {
-    #[serde(rename = "Id")]
     pub id: i64,
-    #[serde(rename = "Name")]
     pub name: String,
     #[serde(rename = "Type")]
     pub record_type: u8,
-    #[serde(rename = "Value")]
     pub value: String,
-    #[serde(rename = "Ttl")]
     pub ttl: u32,
+    pub weight: Option<i32>,
+    pub priority: Option<i32>,
+    pub port: Option<i32>,
+    pub flags: Option<i32>,
+    pub tag: Option<String>,
+    pub accelerated: Option<bool>,
+    pub disabled: Option<bool>,
+    pub comment: Option<String>,
+    pub monitor_type: Option<i32>,
+    pub latency_zone: Option<String>,
+    /// The remaining attributes, such as the geolocation settings.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -41,38 +53,17 @@ pub async fn list_records(
     Ok(response.records)
 }
 
-#[derive(Serialize, Debug)]
-struct UpdateRecordRequest<'a> {
-    #[serde(rename = "Id")]
-    id: i64,
-    #[serde(rename = "Type")]
-    record_type: u8,
-    #[serde(rename = "Value")]
-    value: &'a str,
-    #[serde(rename = "Name")]
-    name: &'a str,
-    #[serde(rename = "Ttl")]
-    ttl: u32,
-}
-
+/// Replaces a record with `record`.
 pub async fn update_record(
     client: &Client,
     zone_id: i64,
-    record_id: i64,
-    record_type: u8,
-    name: &str,
-    ttl: u32,
-    new_ip: &str,
+    record: &BunnyRecord,
 ) -> Result<()> {
+    let record_id = record.id;
+
     client
         .post(format!("{BUNNY_API_URL}/dnszone/{zone_id}/records/{record_id}"))
-        .json(&UpdateRecordRequest {
-            id: record_id,
-            record_type,
-            value: new_ip,
-            name,
-            ttl,
-        })
+        .json(record)
         .send()
         .await?
         .error_for_status()
@@ -80,3 +71,41 @@ pub async fn update_record(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_round_trip() -> Result<()> {
+        let stored = json!({
+            "Id": 42,
+            "Type": 0,
+            "Ttl": 300,
+            "Value": "198.51.100.1",
+            "Name": "www",
+            "Weight": 100,
+            "Priority": 0,
+            "Port": 0,
+            "Flags": 0,
+            "Tag": "",
+            "Accelerated": true,
+            "AcceleratedPullZoneId": 7,
+            "Disabled": false,
+            "Comment": "Home",
+            "MonitorType": 1,
+            "LatencyZone": "DE",
+            "SmartRoutingType": 2,
+            "GeolocationLatitude": 52.37,
+            "GeolocationLongitude": 4.89,
+            "EnviromentalVariables": [],
+        });
+
+        let record: BunnyRecord = serde_json::from_value(stored.clone())?;
+        assert_eq!(record.comment.as_deref(), Some("Home"));
+        assert_eq!(serde_json::to_value(&record)?, stored);
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use color_eyre::Result;
 use color_eyre::eyre::eyre;
-use reqwest::Client;
+use reqwest::{Client, Url};
 use serde::Deserialize;
 
 use super::BUNNY_API_URL;
@@ -21,13 +21,25 @@ struct ListZonesResponse {
     has_more_items: bool,
 }
 
-pub async fn list_zones(client: &Client) -> Result<Vec<BunnyZone>> {
+/// Lists the zones, or only those whose domain contains `search`.
+pub async fn list_zones(
+    client: &Client,
+    search: Option<&str>,
+) -> Result<Vec<BunnyZone>> {
     let mut all_zones = Vec::new();
     let mut page = 1u32;
 
     loop {
+        let mut url = Url::parse(&format!("{BUNNY_API_URL}/dnszone"))?;
+        url.query_pairs_mut()
+            .append_pair("page", &page.to_string())
+            .append_pair("perPage", "1000");
+        if let Some(search) = search {
+            url.query_pairs_mut().append_pair("search", search);
+        }
+
         let response: ListZonesResponse = client
-            .get(format!("{BUNNY_API_URL}/dnszone?page={page}&perPage=1000"))
+            .get(url)
             .send()
             .await?
             .error_for_status()
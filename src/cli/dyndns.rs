@@ -1,23 +1,30 @@
-use std::collections::HashMap;
-use std::net::Ipv4Addr;
+use std::collections::BTreeSet;
+use std::net::IpAddr;
 
 use camino::Utf8PathBuf;
 use clap::Parser;
 use color_eyre::Result;
 use color_eyre::eyre::eyre;
-use tracing::{debug, info, trace};
+use tracing::{debug, info, trace, warn};
 
-use crate::config::{ApplicationConfigLoader, ProviderConfig, ZoneConfig};
-use crate::get_public_ip_address;
+use crate::config::{
+    ApplicationConfigLoader, DEFAULT_IP_SOURCE, PublicIpConfig, ZoneConfig,
+};
 use crate::ip_cache::{IpCacheReader, IpCacheResult, IpCacheWriter};
+use crate::ip_family::IpFamily;
+use crate::ip_policy::check_address;
+use crate::ip_source::detect_public_ip_address;
 use crate::provider::bunny::BunnyProvider;
 use crate::provider::cloudflare::CloudflareProvider;
+use crate::provider::digitalocean::DigitalOceanProvider;
+use crate::provider::hetzner::HetznerProvider;
+use crate::provider::route53::Route53Provider;
 use crate::provider::{DnsProvider, Zone};
 use crate::state::{ApplicationState, ApplicationStateBuilder};
 
 #[allow(clippy::doc_markdown)]
 #[derive(Debug, Parser)]
-/// Dynamic DNS for Cloudflare and bunny.net
+/// Dynamic DNS for Cloudflare, bunny.net, Hetzner, DigitalOcean and Route 53
 struct Args {
     #[arg(short, long)]
     /// Configuration file location. Defaults to
@@ -28,10 +35,11 @@ struct Args {
     /// configuration file, with a .cache extension.
     ip_cache: Option<Utf8PathBuf>,
 
-    /// The desired IP address. Defaults to the IP address determined via the
-    /// `public_ip_url` in the configuration.
+    /// The desired IP address. May be given once for IPv4 and once for IPv6.
+    /// Defaults to the IP addresses determined via the `public_ip` sources in
+    /// the configuration.
     #[arg(short, long)]
-    ip_address: Option<Ipv4Addr>,
+    ip_address: Vec<IpAddr>,
 
     /// Shows what would happen, but doesn't change any settings.
     #[arg(short, long)]
@@ -49,6 +57,8 @@ pub async fn main() -> Result<()> {
     let args = Args::parse();
     trace!("Parsed args:\n{:#?}", args);
 
+    check_ip_addresses(&args.ip_address)?;
+
     let config_path =
         args.config.unwrap_or(ApplicationConfigLoader::default_config_file()?);
 
@@ -59,9 +69,10 @@ pub async fn main() -> Result<()> {
         info!("Preview mode — no changes will be made.");
     }
 
-    if config.cloudflare().is_none() && config.bunny().is_none() {
+    if !config.has_provider() {
         return Err(eyre!(
-            "No provider configured. Add a [cloudflare] or [bunny] section to your config."
+            "No provider configured. Add a [cloudflare], [bunny], [hetzner], \
+             [digitalocean] or [route53] section to your config."
         ));
     }
 
@@ -70,29 +81,88 @@ pub async fn main() -> Result<()> {
     let ip_cache = IpCacheReader::load(&ip_cache_path)?;
     debug!("IP cache:\n{:#?}", ip_cache);
 
-    let public_ip_address = if let Some(ip) = args.ip_address {
-        ip
+    let mut state_builder = ApplicationStateBuilder::default();
+
+    let ip_sources = config.ip_sources();
+    let used_ip_sources: BTreeSet<&str> = config
+        .zones()
+        .flat_map(|z| z.ip_source_families().into_iter().map(|(s, _)| s))
+        .collect();
+
+    for ip_source in &used_ip_sources {
+        if !ip_sources.contains_key(*ip_source) {
+            return Err(eyre!(
+                "IP source '{ip_source}' is not configured. Add an [ip_sources.{ip_source}] section to your config."
+            ));
+        }
+    }
+
+    if args.ip_address.is_empty() {
+        for ip_source in used_ip_sources {
+            let ip_source_config = &ip_sources[ip_source];
+
+            if ip_source_config.sources(IpFamily::Ipv4).is_empty()
+                && ip_source_config.sources(IpFamily::Ipv6).is_empty()
+            {
+                let section = if ip_source == DEFAULT_IP_SOURCE {
+                    "public_ip".to_owned()
+                } else {
+                    format!("ip_sources.{ip_source}")
+                };
+
+                return Err(eyre!(
+                    "No public IP sources configured for '{ip_source}'. Add sources to the [{section}] section of your config."
+                ));
+            }
+
+            detect_addresses(ip_source, ip_source_config, &mut state_builder)
+                .await?;
+        }
     } else {
-        get_public_ip_address(config.public_ip_url()).await?
-    };
+        for ip_source in used_ip_sources {
+            for ip_address in &args.ip_address {
+                state_builder.public_ip_address(ip_source, *ip_address);
+            }
+        }
+    }
 
-    let mut state = ApplicationStateBuilder::default()
+    let mut state = state_builder
         .config_path(config_path)
         .ip_cache(ip_cache)
         .ip_cache_path(ip_cache_path)
-        .public_ip_address(public_ip_address)
         .preview(args.preview)
         .force(args.force)
         .build()?;
 
+    // Refuse before any provider is called, so a refused address doesn't
+    // leave some zones updated and others not.
+    for zone_config in config.zones() {
+        check_addresses(zone_config, &state)?;
+    }
+
     if let Some(cf_config) = config.cloudflare() {
-        let provider = CloudflareProvider::new(cf_config.token())?;
-        run_provider(&provider, cf_config, &mut state).await?;
+        let provider = CloudflareProvider::new(cf_config)?;
+        run_provider(&provider, cf_config.zones(), &mut state).await?;
     }
 
     if let Some(bunny_config) = config.bunny() {
         let provider = BunnyProvider::new(bunny_config.token())?;
-        run_provider(&provider, bunny_config, &mut state).await?;
+        run_provider(&provider, bunny_config.zones(), &mut state).await?;
+    }
+
+    if let Some(hetzner_config) = config.hetzner() {
+        let provider = HetznerProvider::new(hetzner_config.token())?;
+        run_provider(&provider, hetzner_config.zones(), &mut state).await?;
+    }
+
+    if let Some(do_config) = config.digitalocean() {
+        let provider = DigitalOceanProvider::new(do_config.token())?;
+        run_provider(&provider, do_config.zones(), &mut state).await?;
+    }
+
+    if let Some(route53_config) = config.route53() {
+        let provider = Route53Provider::new(route53_config)?;
+        run_provider(&provider, route53_config.zones(), &mut state).await?;
     }
 
     if state.preview {
@@ -104,25 +174,74 @@ pub async fn main() -> Result<()> {
     Ok(())
 }
 
+/// Checks that at most one address of each family is given.
+fn check_ip_addresses(ip_addresses: &[IpAddr]) -> Result<()> {
+    for family in [IpFamily::Ipv4, IpFamily::Ipv6] {
+        let count =
+            ip_addresses.iter().filter(|a| IpFamily::of(**a) == family).count();
+
+        if count > 1 {
+            return Err(eyre!(
+                "--ip-address may only be given once for {family}."
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Records the public addresses `ip_source` reports. An IPv6 failure only
+/// skips the AAAA records, so the A records are still kept up to date.
+async fn detect_addresses(
+    ip_source: &str,
+    ip_source_config: &PublicIpConfig,
+    state_builder: &mut ApplicationStateBuilder,
+) -> Result<()> {
+    for family in [IpFamily::Ipv4, IpFamily::Ipv6] {
+        match detect_public_ip_address(ip_source_config, family).await {
+            Ok(Some(ip_address)) => {
+                state_builder.public_ip_address(ip_source, ip_address);
+            },
+            Ok(None) => {},
+            Err(error) if family == IpFamily::Ipv6 => {
+                warn!(
+                    "Unable to determine the IPv6 address of '{ip_source}', skipping its AAAA records: {error:#}"
+                );
+            },
+            Err(error) => return Err(error),
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks every address `zone_config` would publish against its policy.
+fn check_addresses(
+    zone_config: &ZoneConfig,
+    state: &ApplicationState,
+) -> Result<()> {
+    for (ip_source, family) in zone_config.ip_source_families() {
+        if let Some(public_ip_address) =
+            state.public_ip_address(ip_source, family)
+        {
+            check_address(
+                public_ip_address,
+                zone_config.private_addresses(),
+                &zone_config.name,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
 async fn run_provider<P: DnsProvider>(
     provider: &P,
-    provider_config: &ProviderConfig,
+    zone_configs: &[ZoneConfig],
     state: &mut ApplicationState,
 ) -> Result<()> {
-    let zone_list = provider.list_zones().await?;
-    let zone_map: HashMap<String, Zone> =
-        zone_list.into_iter().map(|z| (z.name.clone(), z)).collect();
-
-    debug!("Retrieved zones: {:#?}", zone_map.keys().collect::<Vec<_>>());
-
-    for zone_config in provider_config.zones() {
-        let zone = zone_map.get(&zone_config.name).cloned().unwrap_or_else(|| {
-            tracing::warn!(
-                "Zone '{}' not found in provider's zone list — treating as zone ID directly",
-                zone_config.name
-            );
-            Zone { id: zone_config.name.clone(), name: zone_config.name.clone() }
-        });
+    for zone_config in zone_configs {
+        let zone = provider.find_configured_zone(zone_config).await?;
         handle_zone(provider, &zone, zone_config, state).await?;
     }
 
@@ -144,53 +263,104 @@ async fn handle_zone<P: DnsProvider>(
 
     info!("Handling zone '{}'", zone.name);
 
-    let public_ip_address = state.public_ip_address;
-    let result = state.ip_cache.handle_ip(&zone.id, public_ip_address);
+    let mut to_update = Vec::new();
 
-    match result {
-        IpCacheResult::Unchanged => {
-            if state.force {
+    for (ip_source, family) in zone_config.ip_source_families() {
+        let label = if ip_source == DEFAULT_IP_SOURCE {
+            format!("{family} address")
+        } else {
+            format!("{family} address of '{ip_source}'")
+        };
+
+        let Some(public_ip_address) =
+            state.public_ip_address(ip_source, family)
+        else {
+            warn!("No {label} known, skipping its records");
+            continue;
+        };
+
+        let result =
+            state.ip_cache.handle_ip(&zone.id, ip_source, public_ip_address);
+
+        match result {
+            IpCacheResult::Unchanged => {
+                if state.force {
+                    info!(
+                        "{label} unchanged: '{public_ip_address}', forcing update"
+                    );
+                } else {
+                    info!("{label} unchanged: '{public_ip_address}'");
+                    continue;
+                }
+            },
+            IpCacheResult::New => {
+                info!("{label} on first run: '{public_ip_address}'");
+            },
+            IpCacheResult::Changed { previous_ip_address } => {
                 info!(
-                    "IP address unchanged: '{public_ip_address}', forcing update"
+                    "{label} updated: '{previous_ip_address}' => '{public_ip_address}'"
                 );
-            } else {
-                info!("IP address unchanged: '{public_ip_address}'");
-                return Ok(());
-            }
-        },
-        IpCacheResult::New => {
-            info!("IP address on first run: '{public_ip_address}'");
-        },
-        IpCacheResult::Changed { previous_ip_address } => {
-            info!(
-                "IP address updated: '{previous_ip_address}' => '{public_ip_address}'"
-            );
-        },
-    }
-
-    let records = provider.list_records(zone).await?;
+            },
+        }
+
+        to_update.push((ip_source, family));
+    }
+
+    if to_update.is_empty() {
+        return Ok(());
+    }
+
+    let records = provider.list_selected_records(zone, zone_config).await?;
     debug!("Retrieved records for '{}':\n{:#?}", zone.name, records);
 
     let records_to_update: Vec<_> = records
         .iter()
-        .filter(|r| zone_config.is_record_selected(&r.name, r.record_type))
+        .filter_map(|r| {
+            let record_config =
+                zone_config.record_config(&r.name, r.record_type)?;
+            let ip_source = zone_config.record_ip_source(record_config);
+            let family = IpFamily::for_record_type(r.record_type)?;
+
+            to_update.contains(&(ip_source, family)).then_some((
+                r,
+                record_config,
+                ip_source,
+                family,
+            ))
+        })
         .collect();
 
     debug!("Updating {} records:", records_to_update.len());
-    for record in &records_to_update {
+    for (record, ..) in &records_to_update {
         debug!("{:>4}: {}", record.record_type, record.name);
     }
 
-    for record in records_to_update {
+    let mut updates = Vec::new();
+
+    for (record, record_config, ip_source, family) in records_to_update {
+        // The family follows from the record type, so an address is never
+        // written into a record of the other family.
+        let public_ip_address =
+            state.public_ip_address(ip_source, family).ok_or(eyre!(
+                "No public address for {} record '{}'.",
+                record.record_type,
+                record.name
+            ))?;
+
+        let target_address = record_config.target_address(public_ip_address);
+        let record = record_config.apply_settings(record);
+
         if state.preview {
-            info!("Would update {}.", record.name);
+            info!("Would update {} to '{target_address}'.", record.name);
         } else {
-            info!("Updating {}...", record.name);
-            provider
-                .update_record(zone, record, &public_ip_address.to_string())
-                .await?;
+            info!("Updating {} to '{target_address}'...", record.name);
+            updates.push((record, target_address.to_string()));
         }
     }
 
+    if !updates.is_empty() {
+        provider.update_records(zone, &updates).await?;
+    }
+
     Ok(())
 }
@@ -5,6 +5,9 @@ use color_eyre::Result;
 use crate::config::ApplicationConfigLoader;
 use crate::provider::bunny::BunnyProvider;
 use crate::provider::cloudflare::CloudflareProvider;
+use crate::provider::digitalocean::DigitalOceanProvider;
+use crate::provider::hetzner::HetznerProvider;
+use crate::provider::route53::Route53Provider;
 use crate::provider::{DnsProvider, DnsRecord, DnsRecordType};
 
 #[derive(Parser)]
@@ -28,7 +31,7 @@ pub async fn main() -> Result<()> {
 
     if let Some(cf_config) = config.cloudflare() {
         println!("cloudflare:");
-        let provider = CloudflareProvider::new(cf_config.token())?;
+        let provider = CloudflareProvider::new(cf_config)?;
         print_provider_zones(&provider, args.verbosity).await?;
         println!();
     }
@@ -40,6 +43,27 @@ pub async fn main() -> Result<()> {
         println!();
     }
 
+    if let Some(hetzner_config) = config.hetzner() {
+        println!("hetzner:");
+        let provider = HetznerProvider::new(hetzner_config.token())?;
+        print_provider_zones(&provider, args.verbosity).await?;
+        println!();
+    }
+
+    if let Some(do_config) = config.digitalocean() {
+        println!("digitalocean:");
+        let provider = DigitalOceanProvider::new(do_config.token())?;
+        print_provider_zones(&provider, args.verbosity).await?;
+        println!();
+    }
+
+    if let Some(route53_config) = config.route53() {
+        println!("route53:");
+        let provider = Route53Provider::new(route53_config)?;
+        print_provider_zones(&provider, args.verbosity).await?;
+        println!();
+    }
+
     Ok(())
 }
 
@@ -85,6 +109,7 @@ struct DnsRecordFormatter {
     name: usize,
     record_type: usize,
     content: usize,
+    ttl: usize,
 }
 
 impl DnsRecordFormatter {
@@ -93,25 +118,52 @@ impl DnsRecordFormatter {
             Self {
                 id: acc.id.max(r.id.len()),
                 name: acc.name.max(r.name.len()),
-                record_type: acc
-                    .record_type
-                    .max(r.record_type.to_string().len()),
+                record_type: acc.record_type.max(Self::record_type(r).len()),
                 content: acc.content.max(r.content.len()),
+                ttl: acc.ttl.max(Self::ttl(r).len()),
             }
         })
     }
 
+    /// The type, or the provider's own name for it if it has no variant.
+    fn record_type(r: &DnsRecord) -> String {
+        if r.record_type == DnsRecordType::MISC {
+            r.raw_type.clone()
+        } else {
+            r.record_type.to_string()
+        }
+    }
+
+    fn ttl(r: &DnsRecord) -> String {
+        r.ttl.map(|ttl| ttl.to_string()).unwrap_or_default()
+    }
+
     fn print(&self, r: &DnsRecord) {
-        println!(
-            "    {id:id_w$} {name:name_w$} {rt:rt_w$} {content:content_w$}",
+        let mut extras = Vec::new();
+        if r.proxied == Some(true) {
+            extras.push("proxied".to_owned());
+        }
+        if let Some(tags) = r.tags.as_ref().filter(|t| !t.is_empty()) {
+            extras.push(format!("tags: {}", tags.join(", ")));
+        }
+        if let Some(comment) = &r.comment {
+            extras.push(format!("# {comment}"));
+        }
+
+        let line = format!(
+            "    {id:id_w$} {name:name_w$} {rt:rt_w$} {content:content_w$} {ttl:>ttl_w$} {extras}",
             id = r.id,
             id_w = self.id,
             name = r.name,
             name_w = self.name,
-            rt = r.record_type,
+            rt = Self::record_type(r),
             rt_w = self.record_type,
             content = r.content,
             content_w = self.content,
+            ttl = Self::ttl(r),
+            ttl_w = self.ttl,
+            extras = extras.join("  "),
         );
+        println!("{}", line.trim_end());
     }
 }
@@ -1,3 +1,4 @@
 pub mod dyndns;
 pub mod list_zones;
 pub mod public_ip;
+pub mod verify_token;
@@ -1,16 +1,52 @@
+use std::net::IpAddr;
+use std::time::Instant;
+
 use camino::Utf8PathBuf;
 use clap::Parser;
 use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use futures_util::future::join_all;
+use indexmap::IndexMap;
+use serde::Serialize;
 
-use crate::config::ApplicationConfigLoader;
-use crate::get_public_ip_address;
+use crate::config::{ApplicationConfigLoader, IpSourceConfig};
+use crate::ip_family::IpFamily;
 
 #[derive(Parser)]
-/// Show the current public IP address.
+/// Query every configured IP source and show the public IP addresses they
+/// report.
 struct Args {
     /// Configuration file location. Defaults to
     /// ~/.config/ryndns/ryndns.toml or /etc/ryndns/ryndns.toml when running as root.
     config: Option<Utf8PathBuf>,
+
+    /// Print the results as JSON.
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct SourceResult {
+    ip_source: String,
+    source: String,
+    family: IpFamily,
+    address: Option<IpAddr>,
+    latency_ms: u128,
+    error: Option<String>,
+}
+
+/// Sources of the same IP source and family that report different addresses.
+#[derive(Debug, Serialize)]
+struct Disagreement {
+    ip_source: String,
+    family: IpFamily,
+    addresses: Vec<IpAddr>,
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    sources: Vec<SourceResult>,
+    disagreements: Vec<Disagreement>,
 }
 
 pub async fn main() -> Result<()> {
@@ -23,11 +59,171 @@ pub async fn main() -> Result<()> {
 
     let config = ApplicationConfigLoader::load_config_from(&config_path)?;
 
-    let ip_url = config.public_ip_url();
+    let mut queries = Vec::new();
 
-    let public_ip = get_public_ip_address(ip_url).await?;
+    for (name, ip_source_config) in config.ip_sources() {
+        for family in [IpFamily::Ipv4, IpFamily::Ipv6] {
+            for source in ip_source_config.sources(family) {
+                let name = name.clone();
+                let ip_source_config = ip_source_config.clone();
+                let source = IpSourceConfig::source(source);
 
-    println!("Your public IP address is {public_ip}");
+                queries.push(async move {
+                    // A single attempt, so the latency isn't inflated by
+                    // retries and their backoff.
+                    let start = Instant::now();
+                    let result =
+                        source.query_attempt(family, &ip_source_config).await;
+
+                    SourceResult {
+                        ip_source: name,
+                        source: source.to_string(),
+                        family,
+                        address: result.as_ref().ok().copied(),
+                        latency_ms: start.elapsed().as_millis(),
+                        error: result.err().map(|e| format!("{e:#}")),
+                    }
+                });
+            }
+        }
+    }
+
+    let sources = join_all(queries).await;
+
+    if sources.is_empty() {
+        return Err(eyre!("No public IP sources configured."));
+    }
+
+    let report =
+        Report { disagreements: find_disagreements(&sources), sources };
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_report(&report);
+    }
+
+    if report.sources.iter().all(|s| s.address.is_none()) {
+        return Err(eyre!("None of the sources returned an address."));
+    }
 
     Ok(())
 }
+
+fn find_disagreements(sources: &[SourceResult]) -> Vec<Disagreement> {
+    let mut addresses: IndexMap<(&str, IpFamily), Vec<IpAddr>> =
+        IndexMap::new();
+
+    for source in sources {
+        if let Some(address) = source.address {
+            let entry = addresses
+                .entry((source.ip_source.as_str(), source.family))
+                .or_default();
+
+            if !entry.contains(&address) {
+                entry.push(address);
+            }
+        }
+    }
+
+    addresses
+        .into_iter()
+        .filter(|(_, addresses)| addresses.len() > 1)
+        .map(|((ip_source, family), addresses)| {
+            Disagreement { ip_source: ip_source.to_owned(), family, addresses }
+        })
+        .collect()
+}
+
+fn print_report(report: &Report) {
+    let rows: Vec<[String; 6]> = report
+        .sources
+        .iter()
+        .map(|s| {
+            [
+                s.ip_source.clone(),
+                s.source.clone(),
+                s.family.to_string(),
+                s.address.map(|a| a.to_string()).unwrap_or_default(),
+                format!("{} ms", s.latency_ms),
+                s.error.clone().unwrap_or_default(),
+            ]
+        })
+        .collect();
+
+    let header =
+        ["ip_source", "source", "family", "address", "latency", "error"]
+            .map(str::to_owned);
+
+    let widths = std::iter::once(&header)
+        .chain(&rows)
+        .fold([0; 6], |acc, r| std::array::from_fn(|i| acc[i].max(r[i].len())));
+
+    for row in std::iter::once(&header).chain(&rows) {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+
+        println!("{}", line.trim_end());
+    }
+
+    for disagreement in &report.disagreements {
+        println!();
+        println!(
+            "Sources of '{}' disagree on the {} address: {}",
+            disagreement.ip_source,
+            disagreement.family,
+            disagreement
+                .addresses
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn result(
+        ip_source: &str,
+        family: IpFamily,
+        address: Option<&str>,
+    ) -> SourceResult {
+        SourceResult {
+            ip_source: ip_source.to_owned(),
+            source: "https://example.ip".to_owned(),
+            family,
+            address: address.map(|a| a.parse().unwrap()),
+            latency_ms: 0,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_find_disagreements() {
+        let sources = [
+            result("default", IpFamily::Ipv4, Some("198.51.100.1")),
+            result("default", IpFamily::Ipv4, Some("198.51.100.2")),
+            result("default", IpFamily::Ipv4, Some("198.51.100.1")),
+            result("default", IpFamily::Ipv4, None),
+            result("default", IpFamily::Ipv6, Some("2001:db8::1")),
+            result("wan2", IpFamily::Ipv4, Some("203.0.113.1")),
+        ];
+
+        let disagreements = find_disagreements(&sources);
+
+        assert_eq!(disagreements.len(), 1);
+        assert_eq!(disagreements[0].ip_source, "default");
+        assert_eq!(disagreements[0].family, IpFamily::Ipv4);
+        assert_eq!(disagreements[0].addresses, [
+            "198.51.100.1".parse::<IpAddr>().unwrap(),
+            "198.51.100.2".parse().unwrap()
+        ]);
+    }
+}
@@ -0,0 +1,238 @@
+use camino::Utf8PathBuf;
+use clap::Parser;
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+
+use crate::config::{ApplicationConfigLoader, ZoneConfig};
+use crate::provider::bunny::BunnyProvider;
+use crate::provider::cloudflare::CloudflareProvider;
+use crate::provider::digitalocean::DigitalOceanProvider;
+use crate::provider::hetzner::HetznerProvider;
+use crate::provider::route53::Route53Provider;
+use crate::provider::{DnsProvider, Zone};
+
+#[derive(Parser)]
+/// Check that the configured tokens are valid and can read and edit the
+/// records of every configured zone, without changing anything.
+struct Args {
+    /// Configuration file location. Defaults to
+    /// ~/.config/ryndns/ryndns.toml or /etc/ryndns/ryndns.toml when running as root.
+    config: Option<Utf8PathBuf>,
+}
+
+pub async fn main() -> Result<()> {
+    crate::init()?;
+
+    let args = Args::parse();
+    let config_path =
+        args.config.unwrap_or(ApplicationConfigLoader::default_config_file()?);
+    let config = ApplicationConfigLoader::load_config_from(&config_path)?;
+
+    let mut ready = true;
+
+    if let Some(cf_config) = config.cloudflare() {
+        println!("cloudflare:");
+        let provider = CloudflareProvider::new(cf_config)?;
+        ready &= verify_provider(&provider, cf_config.zones()).await;
+        println!();
+    }
+
+    if let Some(bunny_config) = config.bunny() {
+        println!("bunny:");
+        let provider = BunnyProvider::new(bunny_config.token())?;
+        ready &= verify_provider(&provider, bunny_config.zones()).await;
+        println!();
+    }
+
+    if let Some(hetzner_config) = config.hetzner() {
+        println!("hetzner:");
+        let provider = HetznerProvider::new(hetzner_config.token())?;
+        ready &= verify_provider(&provider, hetzner_config.zones()).await;
+        println!();
+    }
+
+    if let Some(do_config) = config.digitalocean() {
+        println!("digitalocean:");
+        let provider = DigitalOceanProvider::new(do_config.token())?;
+        ready &= verify_provider(&provider, do_config.zones()).await;
+        println!();
+    }
+
+    if let Some(route53_config) = config.route53() {
+        println!("route53:");
+        let provider = Route53Provider::new(route53_config)?;
+        ready &= verify_provider(&provider, route53_config.zones()).await;
+        println!();
+    }
+
+    if ready {
+        Ok(())
+    } else {
+        Err(eyre!("Not every configured zone is ready."))
+    }
+}
+
+/// Prints the readiness of the credentials and of every zone, returning
+/// whether all of them are ready.
+async fn verify_provider<P: DnsProvider>(
+    provider: &P,
+    zone_configs: &[ZoneConfig],
+) -> bool {
+    match provider.verify_credentials().await {
+        Ok(description) => println!("  credentials: ok ({description})"),
+        Err(e) => {
+            println!("  credentials: FAILED: {e}");
+            return false;
+        },
+    }
+
+    let max_name = zone_configs.iter().map(|z| z.name.len()).max().unwrap_or(0);
+
+    let mut ready = true;
+
+    for zone_config in zone_configs {
+        let result = match provider.find_configured_zone(zone_config).await {
+            Ok(zone) => verify_zone(provider, &zone, zone_config).await,
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(status) if status.warnings.is_empty() => {
+                println!(
+                    "  {:max_name$}  {}",
+                    zone_config.name,
+                    status.state()
+                );
+            },
+            Ok(status) => {
+                println!(
+                    "  {:max_name$}  {}, but {}",
+                    zone_config.name,
+                    status.state(),
+                    status.warnings.join("; ")
+                );
+            },
+            Err(e) => {
+                println!("  {:max_name$}  FAILED: {e}", zone_config.name);
+                ready = false;
+            },
+        }
+    }
+
+    ready
+}
+
+struct ZoneStatus {
+    /// Whether the provider could check that the records can be edited.
+    edit_access_checked: bool,
+    warnings: Vec<String>,
+}
+
+impl ZoneStatus {
+    fn state(&self) -> &'static str {
+        if self.edit_access_checked {
+            "ready"
+        } else {
+            "readable, edit access not checked"
+        }
+    }
+}
+
+/// Checks read and edit access to `zone`, with a warning for every
+/// configured record that doesn't select an existing record.
+async fn verify_zone<P: DnsProvider>(
+    provider: &P,
+    zone: &Zone,
+    zone_config: &ZoneConfig,
+) -> Result<ZoneStatus> {
+    let records = provider
+        .list_selected_records(zone, zone_config)
+        .await
+        .map_err(|e| eyre!("can't read records: {e}"))?;
+
+    let edit_access_checked = provider
+        .verify_edit_access(zone)
+        .await
+        .map_err(|e| eyre!("can't edit records: {e}"))?;
+
+    let warnings = zone_config
+        .records()
+        .iter()
+        .filter(|record_config| {
+            !records.iter().any(|r| {
+                zone_config.record_config(&r.name, r.record_type)
+                    == Some(*record_config)
+            })
+        })
+        .map(|record_config| {
+            format!(
+                "no {} record matches '{}'",
+                record_config.record_type(),
+                record_config.name()
+            )
+        })
+        .collect();
+
+    Ok(ZoneStatus { edit_access_checked, warnings })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::provider::{DnsRecord, DnsRecordType};
+
+    /// A provider with one zone, holding a single A record.
+    struct StandInProvider;
+
+    impl DnsProvider for StandInProvider {
+        async fn list_zones(&self) -> Result<Vec<Zone>> {
+            Ok(vec![Zone {
+                id: "zone".to_owned(),
+                name: "example.com".to_owned(),
+            }])
+        }
+
+        async fn list_records(&self, _zone: &Zone) -> Result<Vec<DnsRecord>> {
+            Ok(vec![DnsRecord {
+                id: "record".to_owned(),
+                name: "home.example.com".to_owned(),
+                record_type: DnsRecordType::A,
+                raw_type: "A".to_owned(),
+                content: "203.0.113.7".to_owned(),
+                ttl: None,
+                proxied: None,
+                comment: None,
+                tags: None,
+                stored: None,
+            }])
+        }
+
+        async fn update_record(
+            &self,
+            _zone: &Zone,
+            _record: &DnsRecord,
+            _new_ip: &str,
+        ) -> Result<()> {
+            unreachable!("verifying changes nothing")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_zone() -> Result<()> {
+        let zone_config: ZoneConfig = toml::from_str(
+            r#"
+            name = "example.com"
+            records = ["home", { type = "AAAA", name = "home" }]
+            "#,
+        )?;
+        let zone = StandInProvider.find_configured_zone(&zone_config).await?;
+
+        let status = verify_zone(&StandInProvider, &zone, &zone_config).await?;
+
+        // The provider can't check edit access, so the zone isn't ready.
+        assert!(!status.edit_access_checked);
+        assert_eq!(status.state(), "readable, edit access not checked");
+        assert_eq!(status.warnings, ["no AAAA record matches 'home'"]);
+        Ok(())
+    }
+}
@@ -1,7 +1,9 @@
+use color_eyre::Result;
 use color_eyre::eyre::{Error, eyre};
 use serde::Deserialize;
 
 pub mod record;
+pub mod token;
 pub mod zone;
 
 const API_URL: &str = "https://api.cloudflare.com/client/v4";
@@ -21,3 +23,66 @@ struct CloudFlareError {
     pub code: isize,
     pub message: String,
 }
+
+/// Pagination details of a list response.
+#[derive(Deserialize, Debug)]
+struct ResultInfo {
+    page: u32,
+    total_pages: u32,
+}
+
+impl ResultInfo {
+    fn has_more_pages(&self) -> bool {
+        self.page < self.total_pages
+    }
+}
+
+/// Requests every page of a list response, starting from the first, until
+/// its `result_info` reports no more pages.
+async fn get_all_pages<T, F>(
+    mut get_page: impl FnMut(u32) -> F,
+) -> Result<Vec<T>>
+where
+    F: Future<Output = Result<(Vec<T>, Option<ResultInfo>)>>,
+{
+    let mut all_results = Vec::new();
+    let mut page = 1u32;
+
+    loop {
+        let (results, result_info) = get_page(page).await?;
+        all_results.extend(results);
+
+        if !result_info.is_some_and(|info| info.has_more_pages()) {
+            break;
+        }
+        page += 1;
+    }
+
+    Ok(all_results)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_get_all_pages() -> Result<()> {
+        let mut requested = Vec::new();
+
+        let results = get_all_pages(|page| {
+            requested.push(page);
+            let result_info = ResultInfo { page, total_pages: 3 };
+            async move { Ok((vec![page * 10], Some(result_info))) }
+        })
+        .await?;
+
+        assert_eq!(requested, [1, 2, 3]);
+        assert_eq!(results, [10, 20, 30]);
+
+        // Without pagination details, there is only one page.
+        let results =
+            get_all_pages(|page| async move { Ok((vec![page], None)) }).await?;
+        assert_eq!(results, [1]);
+        Ok(())
+    }
+}
@@ -1,15 +1,19 @@
 use color_eyre::Result;
-use reqwest::Client;
+use color_eyre::eyre::eyre;
+use reqwest::{Client, Url};
 use serde::{Deserialize, Serialize};
 
-use super::{API_URL, CloudFlareError, transform_error_responses};
-use crate::provider::DnsRecordType;
+use super::{
+    API_URL, CloudFlareError, ResultInfo, get_all_pages,
+    transform_error_responses,
+};
 
 #[derive(Deserialize, Debug)]
 struct GetRecordsResponse {
     success: bool,
     errors: Vec<CloudFlareError>,
     result: Vec<CloudflareRecord>,
+    result_info: Option<ResultInfo>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -17,63 +21,253 @@ pub struct CloudflareRecord {
     pub id: String,
     pub name: String,
     #[serde(rename = "type")]
-    pub record_type: DnsRecordType,
+    pub record_type: String,
     pub content: String,
+    pub ttl: u32,
+    #[serde(default)]
+    pub proxied: bool,
+    pub comment: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
+/// Lists the records of a zone, restricted by query `filters` such as
+/// `("type", "A")` or `("name.startswith", "www.")`.
 pub async fn get_records(
     client: &Client,
     zone_id: &str,
+    filters: &[(&str, &str)],
 ) -> Result<Vec<CloudflareRecord>> {
+    get_all_pages(|page| {
+        async move {
+            let response = client
+                .get(records_url(zone_id, filters, page)?)
+                .send()
+                .await?
+                .json::<GetRecordsResponse>()
+                .await?;
+
+            if !response.success {
+                return Err(transform_error_responses(&response.errors));
+            }
+
+            Ok((response.result, response.result_info))
+        }
+    })
+    .await
+}
+
+fn records_url(
+    zone_id: &str,
+    filters: &[(&str, &str)],
+    page: u32,
+) -> Result<Url> {
+    let mut url =
+        Url::parse(&format!("{API_URL}/zones/{zone_id}/dns_records"))?;
+    url.query_pairs_mut()
+        .extend_pairs(filters)
+        .append_pair("page", &page.to_string())
+        .append_pair("per_page", "1000");
+
+    Ok(url)
+}
+
+#[derive(Serialize, Debug)]
+pub struct PatchRecordRequest<'c> {
+    pub content: &'c str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxied: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<&'c str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<&'c [String]>,
+}
+
+#[derive(Deserialize, Debug)]
+struct PatchRecordResponse {
+    success: bool,
+    errors: Vec<CloudFlareError>,
+}
+
+pub async fn patch_record(
+    client: &Client,
+    zone_id: &str,
+    record_id: &str,
+    request: &PatchRecordRequest<'_>,
+) -> Result<()> {
     let response = client
-        .get(format!("{API_URL}/zones/{zone_id}/dns_records"))
+        .patch(format!("{API_URL}/zones/{zone_id}/dns_records/{record_id}"))
+        .json(request)
         .send()
         .await?
-        .json::<GetRecordsResponse>()
+        .json::<PatchRecordResponse>()
         .await?;
 
     if response.success {
-        Ok(response.result)
+        Ok(())
     } else {
         Err(transform_error_responses(&response.errors))
     }
 }
 
 #[derive(Serialize, Debug)]
-pub struct PatchRecordRequest<'c> {
-    content: &'c str,
+pub struct BatchPatch<'c> {
+    pub id: &'c str,
+    #[serde(flatten)]
+    pub patch: PatchRecordRequest<'c>,
 }
 
-impl<'c> PatchRecordRequest<'c> {
-    #[must_use]
-    fn new(content: &'c str) -> Self {
-        Self { content }
-    }
+#[derive(Serialize, Debug)]
+struct BatchRequest<'c> {
+    patches: &'c [BatchPatch<'c>],
 }
 
 #[derive(Deserialize, Debug)]
-struct PatchRecordResponse {
+struct BatchResponse {
     success: bool,
     errors: Vec<CloudFlareError>,
 }
 
-pub async fn patch_record(
+/// Applies all `patches` to the records of a zone in one transaction: if one
+/// of them fails, none of the records are changed.
+pub async fn batch_patch_records(
     client: &Client,
     zone_id: &str,
-    record_id: &str,
-    content: &str,
+    patches: &[BatchPatch<'_>],
 ) -> Result<()> {
     let response = client
-        .patch(format!("{API_URL}/zones/{zone_id}/dns_records/{record_id}"))
-        .json(&PatchRecordRequest::new(content))
+        .post(format!("{API_URL}/zones/{zone_id}/dns_records/batch"))
+        .json(&BatchRequest { patches })
         .send()
         .await?
-        .json::<PatchRecordResponse>()
+        .json::<BatchResponse>()
+        .await?;
+
+    if response.success {
+        Ok(())
+    } else {
+        Err(transform_error_responses(&response.errors))
+    }
+}
+
+/// Checks that the credentials can edit the records of a zone, by sending an
+/// empty batch. The batch endpoint requires the "DNS Write" permission, so
+/// only an accepted batch confirms the credentials have it; any other
+/// response fails with the errors Cloudflare returned. An empty batch
+/// changes nothing, whether it is accepted or rejected.
+pub async fn check_edit_access(client: &Client, zone_id: &str) -> Result<()> {
+    let response = client
+        .post(format!("{API_URL}/zones/{zone_id}/dns_records/batch"))
+        .json(&BatchRequest { patches: &[] })
+        .send()
+        .await?
+        .json::<BatchResponse>()
         .await?;
 
+    edit_access(&response)
+}
+
+fn edit_access(response: &BatchResponse) -> Result<()> {
     if response.success {
         Ok(())
+    } else if response.errors.is_empty() {
+        Err(eyre!("the empty batch was rejected without an error"))
     } else {
         Err(transform_error_responses(&response.errors))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_records_url() -> Result<()> {
+        let filters =
+            [("type", "AAAA"), ("name.startswith", "home.example.com")];
+
+        assert_eq!(
+            records_url("zone", &filters, 2)?.as_str(),
+            "https://api.cloudflare.com/client/v4/zones/zone/dns_records\
+             ?type=AAAA&name.startswith=home.example.com&page=2&per_page=1000"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_batch_request() -> Result<()> {
+        let tags = ["home".to_owned()];
+        let patches = [
+            BatchPatch {
+                id: "a",
+                patch: PatchRecordRequest {
+                    content: "203.0.113.7",
+                    ttl: Some(60),
+                    proxied: Some(true),
+                    comment: None,
+                    tags: None,
+                },
+            },
+            BatchPatch {
+                id: "b",
+                patch: PatchRecordRequest {
+                    content: "2001:db8::7",
+                    ttl: None,
+                    proxied: None,
+                    comment: Some("NAS"),
+                    tags: Some(&tags),
+                },
+            },
+        ];
+
+        assert_eq!(
+            serde_json::to_value(BatchRequest { patches: &patches })?,
+            serde_json::json!({
+                "patches": [
+                    {
+                        "id": "a",
+                        "content": "203.0.113.7",
+                        "ttl": 60,
+                        "proxied": true,
+                    },
+                    {
+                        "id": "b",
+                        "content": "2001:db8::7",
+                        "comment": "NAS",
+                        "tags": ["home"],
+                    },
+                ],
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_edit_access() -> Result<()> {
+        let accepted: BatchResponse = serde_json::from_str(
+            r#"{ "success": true, "errors": [], "result": {} }"#,
+        )?;
+        assert!(edit_access(&accepted).is_ok());
+
+        let invalid: BatchResponse = serde_json::from_str(
+            r#"{ "success": false, "errors": [
+                { "code": 1004, "message": "DNS Validation Error" }
+            ] }"#,
+        )?;
+        assert!(edit_access(&invalid).is_err());
+
+        let denied: BatchResponse = serde_json::from_str(
+            r#"{ "success": false, "errors": [
+                { "code": 10000, "message": "Authentication error" }
+            ] }"#,
+        )?;
+        assert!(edit_access(&denied).is_err());
+
+        let unexplained: BatchResponse =
+            serde_json::from_str(r#"{ "success": false, "errors": [] }"#)?;
+        assert!(edit_access(&unexplained).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+use color_eyre::Result;
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{API_URL, CloudFlareError, transform_error_responses};
+
+#[derive(Deserialize, Debug)]
+struct VerifyTokenResponse {
+    success: bool,
+    errors: Vec<CloudFlareError>,
+    result: Option<TokenStatus>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TokenStatus {
+    pub id: String,
+    /// `active`, `disabled` or `expired`.
+    pub status: String,
+    pub expires_on: Option<String>,
+}
+
+pub async fn verify_token(client: &Client) -> Result<TokenStatus> {
+    let response = client
+        .get(format!("{API_URL}/user/tokens/verify"))
+        .send()
+        .await?
+        .json::<VerifyTokenResponse>()
+        .await?;
+
+    match response.result {
+        Some(status) if response.success => Ok(status),
+        _ => Err(transform_error_responses(&response.errors)),
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct GetUserResponse {
+    success: bool,
+    errors: Vec<CloudFlareError>,
+}
+
+/// Fetches the user the credentials belong to, which fails unless they're
+/// valid.
+pub async fn get_user(client: &Client) -> Result<()> {
+    let response = client
+        .get(format!("{API_URL}/user"))
+        .send()
+        .await?
+        .json::<GetUserResponse>()
+        .await?;
+
+    if response.success {
+        Ok(())
+    } else {
+        Err(transform_error_responses(&response.errors))
+    }
+}
@@ -1,14 +1,18 @@
 use color_eyre::Result;
-use reqwest::Client;
+use reqwest::{Client, Url};
 use serde::Deserialize;
 
-use super::{API_URL, CloudFlareError, transform_error_responses};
+use super::{
+    API_URL, CloudFlareError, ResultInfo, get_all_pages,
+    transform_error_responses,
+};
 
 #[derive(Deserialize, Debug)]
 struct ListZonesResponse {
     success: bool,
     errors: Vec<CloudFlareError>,
     result: Vec<ZoneResponse>,
+    result_info: Option<ResultInfo>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -17,17 +21,33 @@ pub struct ZoneResponse {
     pub id: String,
 }
 
-pub async fn list_zones(client: &Client) -> Result<Vec<ZoneResponse>> {
-    let response = client
-        .get(format!("{API_URL}/zones"))
-        .send()
-        .await?
-        .json::<ListZonesResponse>()
-        .await?;
+/// Lists the zones the credentials have access to, restricted by query
+/// `filters` such as `("name", "example.com")` or `("account.id", "...")`.
+pub async fn list_zones(
+    client: &Client,
+    filters: &[(&str, &str)],
+) -> Result<Vec<ZoneResponse>> {
+    get_all_pages(|page| {
+        async move {
+            let mut url = Url::parse(&format!("{API_URL}/zones"))?;
+            url.query_pairs_mut()
+                .extend_pairs(filters)
+                .append_pair("page", &page.to_string())
+                .append_pair("per_page", "50");
 
-    if response.success {
-        Ok(response.result)
-    } else {
-        Err(transform_error_responses(&response.errors))
-    }
+            let response = client
+                .get(url)
+                .send()
+                .await?
+                .json::<ListZonesResponse>()
+                .await?;
+
+            if !response.success {
+                return Err(transform_error_responses(&response.errors));
+            }
+
+            Ok((response.result, response.result_info))
+        }
+    })
+    .await
 }
@@ -15,6 +15,7 @@ impl ApplicationConfigLoader {
     ) -> Result<ApplicationConfig> {
         let contents = fs_err::read_to_string(config_path)?;
         let config: ApplicationConfig = toml::from_str(&contents)?;
+        config.validate().map_err(|e| eyre!("{config_path}: {e}"))?;
 
         debug!("Loaded configuration from {config_path}");
 
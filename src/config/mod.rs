@@ -1,26 +1,120 @@
+use std::collections::BTreeSet;
+use std::net::{IpAddr, Ipv6Addr};
+
+use indexmap::IndexMap;
 use serde::Deserialize;
 
-use crate::provider::DnsRecordType;
+use crate::ip_family::IpFamily;
+use crate::provider::{DnsRecord, DnsRecordType};
 
 mod fs;
+mod public_ip;
+mod route53;
 
 pub use fs::ApplicationConfigLoader;
+pub use public_ip::{
+    AddressScope, DnsQueryType, HttpAuth, HttpMethod, HttpSource, IpSource,
+    IpSourceConfig, IpStrategy, PublicIpConfig, ResponseFormat,
+};
+pub use route53::{AwsAccessKey, Route53Config};
+
+const DEFAULT_PREFIX_LENGTH: u8 = 64;
+
+/// The name of the IP source configured in the `[public_ip]` section.
+pub const DEFAULT_IP_SOURCE: &str = "default";
 
 #[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct ApplicationConfig {
-    public_ip_url: String,
-    cloudflare: Option<ProviderConfig>,
+    public_ip_url: Option<String>,
+    public_ip_url_v6: Option<String>,
+    #[serde(default)]
+    public_ip: PublicIpConfig,
+    #[serde(default)]
+    ip_sources: IndexMap<String, PublicIpConfig>,
+    cloudflare: Option<CloudflareConfig>,
     bunny: Option<ProviderConfig>,
+    hetzner: Option<ProviderConfig>,
+    digitalocean: Option<ProviderConfig>,
+    route53: Option<Route53Config>,
 }
 
 impl ApplicationConfig {
+    /// The `[public_ip]` configuration, with `public_ip_url` and
+    /// `public_ip_url_v6` as the first source of their family.
+    #[must_use]
+    pub fn public_ip(&self) -> PublicIpConfig {
+        let mut public_ip = self.public_ip.clone();
+
+        if let Some(url) = &self.public_ip_url {
+            public_ip.prepend_url(IpFamily::Ipv4, url);
+        }
+
+        if let Some(url) = &self.public_ip_url_v6 {
+            public_ip.prepend_url(IpFamily::Ipv6, url);
+        }
+
+        public_ip
+    }
+
+    /// Every IP source by name: the `[public_ip]` section as
+    /// [`DEFAULT_IP_SOURCE`], followed by the `[ip_sources.<name>]` sections.
     #[must_use]
-    pub fn public_ip_url(&self) -> &str {
-        &self.public_ip_url
+    pub fn ip_sources(&self) -> IndexMap<String, PublicIpConfig> {
+        let mut ip_sources =
+            IndexMap::from([(DEFAULT_IP_SOURCE.to_owned(), self.public_ip())]);
+
+        ip_sources.extend(self.ip_sources.clone());
+
+        ip_sources
+    }
+
+    /// Checks the settings that depend on more than one section, like the
+    /// `public_ip_url` shorthands counting towards a consensus.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.ip_sources.contains_key(DEFAULT_IP_SOURCE) {
+            return Err(format!(
+                "[ip_sources.{DEFAULT_IP_SOURCE}] would replace [public_ip], \
+                 configure the default IP source there instead"
+            ));
+        }
+
+        for (name, ip_source) in self.ip_sources() {
+            ip_source
+                .validate()
+                .map_err(|e| format!("IP source '{name}': {e}"))?;
+        }
+
+        for zone in self.zones() {
+            zone.validate()
+                .map_err(|e| format!("zone '{}': {e}", zone.name))?;
+        }
+
+        let others = [self.bunny(), self.hetzner(), self.digitalocean()];
+        let other_zones = others
+            .into_iter()
+            .flatten()
+            .map(ProviderConfig::zones)
+            .chain(self.route53().map(Route53Config::zones))
+            .flatten();
+
+        for zone in other_zones {
+            if let Some(record) =
+                zone.records.iter().find(|r| r.has_cloudflare_settings())
+            {
+                return Err(format!(
+                    "zone '{}': record '{}' sets proxied, comment or tags, \
+                     which only Cloudflare supports",
+                    zone.name,
+                    record.name()
+                ));
+            }
+        }
+
+        Ok(())
     }
 
     #[must_use]
-    pub fn cloudflare(&self) -> Option<&ProviderConfig> {
+    pub fn cloudflare(&self) -> Option<&CloudflareConfig> {
         self.cloudflare.as_ref()
     }
 
@@ -28,6 +122,44 @@ impl ApplicationConfig {
     pub fn bunny(&self) -> Option<&ProviderConfig> {
         self.bunny.as_ref()
     }
+
+    #[must_use]
+    pub fn hetzner(&self) -> Option<&ProviderConfig> {
+        self.hetzner.as_ref()
+    }
+
+    #[must_use]
+    pub fn digitalocean(&self) -> Option<&ProviderConfig> {
+        self.digitalocean.as_ref()
+    }
+
+    #[must_use]
+    pub fn route53(&self) -> Option<&Route53Config> {
+        self.route53.as_ref()
+    }
+
+    /// Whether any provider is configured.
+    #[must_use]
+    pub fn has_provider(&self) -> bool {
+        self.cloudflare.is_some()
+            || self.bunny.is_some()
+            || self.hetzner.is_some()
+            || self.digitalocean.is_some()
+            || self.route53.is_some()
+    }
+
+    /// The zones of every configured provider.
+    pub fn zones(&self) -> impl Iterator<Item = &ZoneConfig> {
+        let cloudflare = self.cloudflare().map(CloudflareConfig::zones);
+        let others = [self.bunny(), self.hetzner(), self.digitalocean()];
+        let route53 = self.route53().map(Route53Config::zones);
+
+        cloudflare
+            .into_iter()
+            .chain(others.into_iter().flatten().map(ProviderConfig::zones))
+            .chain(route53)
+            .flatten()
+    }
 }
 
 #[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
@@ -48,28 +180,196 @@ impl ProviderConfig {
     }
 }
 
+/// The `[cloudflare]` section, which authenticates with either an API token
+/// or the legacy global API key.
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(try_from = "RawCloudflareConfig")]
+pub struct CloudflareConfig {
+    auth: CloudflareAuth,
+    account_id: Option<String>,
+    zones: Vec<ZoneConfig>,
+}
+
+impl CloudflareConfig {
+    #[must_use]
+    pub fn auth(&self) -> &CloudflareAuth {
+        &self.auth
+    }
+
+    /// The account zones are looked up in, for credentials with access to
+    /// several accounts.
+    #[must_use]
+    pub fn account_id(&self) -> Option<&str> {
+        self.account_id.as_deref()
+    }
+
+    #[must_use]
+    pub fn zones(&self) -> &[ZoneConfig] {
+        &self.zones
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CloudflareAuth {
+    /// An API token, sent as `Authorization: Bearer`.
+    Token(String),
+    /// The global API key of an account, sent as `X-Auth-Key` along with its
+    /// email address as `X-Auth-Email`.
+    GlobalKey { email: String, api_key: String },
+}
+
+#[derive(Deserialize)]
+struct RawCloudflareConfig {
+    token: Option<String>,
+    email: Option<String>,
+    api_key: Option<String>,
+    account_id: Option<String>,
+    zones: Vec<ZoneConfig>,
+}
+
+impl TryFrom<RawCloudflareConfig> for CloudflareConfig {
+    type Error = String;
+
+    fn try_from(raw: RawCloudflareConfig) -> Result<Self, Self::Error> {
+        let auth = match (raw.token, raw.email, raw.api_key) {
+            (Some(token), None, None) => CloudflareAuth::Token(token),
+            (None, Some(email), Some(api_key)) => {
+                CloudflareAuth::GlobalKey { email, api_key }
+            },
+            (None, None, None) => {
+                return Err(
+                    "set either `token`, or `email` and `api_key`".to_owned()
+                );
+            },
+            (Some(_), ..) => {
+                return Err("set either `token`, or `email` and `api_key`, \
+                            not both"
+                    .to_owned());
+            },
+            (None, ..) => {
+                return Err(
+                    "`email` and `api_key` must be set together".to_owned()
+                );
+            },
+        };
+
+        Ok(Self { auth, account_id: raw.account_id, zones: raw.zones })
+    }
+}
+
 #[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct ZoneConfig {
     pub name: String,
     records: Vec<RecordConfig>,
+    #[serde(default)]
+    private_addresses: PrivateAddressPolicy,
+    ip_source: Option<String>,
 }
 
 impl ZoneConfig {
+    /// Checks that every record can be updated with an address.
+    fn validate(&self) -> Result<(), String> {
+        for record in &self.records {
+            let name = record.name();
+
+            if IpFamily::for_record_type(record.record_type()).is_none() {
+                return Err(format!(
+                    "record '{name}' has type {}, only A and AAAA records can \
+                     be updated",
+                    record.record_type()
+                ));
+            }
+
+            if record.interface_id().is_some()
+                && record.record_type() != DnsRecordType::AAAA
+            {
+                return Err(format!(
+                    "record '{name}' has an interface ID, which is only \
+                     supported on AAAA records"
+                ));
+            }
+
+            if record.prefix_length() > 128 {
+                return Err(format!(
+                    "record '{name}' has a prefix length over 128"
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The name of the IP source this zone follows.
+    #[must_use]
+    pub fn ip_source(&self) -> &str {
+        self.ip_source.as_deref().unwrap_or(DEFAULT_IP_SOURCE)
+    }
+
+    /// The name of the IP source `record` follows, which defaults to the
+    /// source of the zone.
+    #[must_use]
+    pub fn record_ip_source<'a>(&'a self, record: &'a RecordConfig) -> &'a str {
+        record.ip_source().unwrap_or(self.ip_source())
+    }
+
+    #[must_use]
+    pub fn private_addresses(&self) -> PrivateAddressPolicy {
+        self.private_addresses
+    }
+
     #[must_use]
     pub fn records(&self) -> &[RecordConfig] {
         &self.records
     }
 
+    /// The IP sources and address families the records of this zone follow.
+    #[must_use]
+    pub fn ip_source_families(&self) -> BTreeSet<(&str, IpFamily)> {
+        self.records
+            .iter()
+            .filter_map(|r| {
+                Some((
+                    self.record_ip_source(r),
+                    IpFamily::for_record_type(r.record_type())?,
+                ))
+            })
+            .collect()
+    }
+
     #[must_use]
     pub fn is_record_selected(
         &self,
         record_name: &str,
         record_type: DnsRecordType,
     ) -> bool {
-        self.records.iter().any(|r| r.matches(record_name, record_type))
+        self.record_config(record_name, record_type).is_some()
+    }
+
+    /// The first record configuration that selects the given record.
+    #[must_use]
+    pub fn record_config(
+        &self,
+        record_name: &str,
+        record_type: DnsRecordType,
+    ) -> Option<&RecordConfig> {
+        self.records.iter().find(|r| r.matches(record_name, record_type))
     }
 }
 
+/// What to do when the public address is private, shared (CGNAT), link-local
+/// or loopback.
+#[derive(Deserialize, Debug, PartialEq, Eq, Default, Copy, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum PrivateAddressPolicy {
+    /// Fail without changing any records.
+    #[default]
+    Refuse,
+    /// Log a warning and update the records.
+    Warn,
+    /// Update the records, for internal zones.
+    Allow,
+}
+
 #[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
 #[serde(untagged)]
 pub enum RecordConfig {
@@ -77,6 +377,24 @@ pub enum RecordConfig {
         #[serde(rename = "type", default)]
         record_type: DnsRecordType,
         name: String,
+        /// For AAAA records of other hosts behind a delegated prefix: the
+        /// host part of the address, combined with the prefix of the public
+        /// IPv6 address.
+        interface_id: Option<Ipv6Addr>,
+        /// Length of the prefix taken from the public IPv6 address. Defaults
+        /// to 64.
+        prefix_length: Option<u8>,
+        /// The name of the IP source to follow instead of the zone's.
+        ip_source: Option<String>,
+        /// Time to live in seconds, set on every update. On Cloudflare, 1
+        /// means automatic.
+        ttl: Option<u32>,
+        /// Cloudflare only: whether traffic is proxied through Cloudflare.
+        proxied: Option<bool>,
+        /// Cloudflare only: the comment on the record.
+        comment: Option<String>,
+        /// Cloudflare only: the tags on the record, as `name:value`.
+        tags: Option<Vec<String>>,
     },
     Name(String),
 }
@@ -104,6 +422,82 @@ impl RecordConfig {
             RecordConfig::Name(_) => DnsRecordType::A,
         }
     }
+
+    #[must_use]
+    pub fn ip_source(&self) -> Option<&str> {
+        match self {
+            RecordConfig::Full { ip_source, .. } => ip_source.as_deref(),
+            RecordConfig::Name(_) => None,
+        }
+    }
+
+    #[must_use]
+    pub fn interface_id(&self) -> Option<Ipv6Addr> {
+        match self {
+            RecordConfig::Full { interface_id, .. } => *interface_id,
+            RecordConfig::Name(_) => None,
+        }
+    }
+
+    #[must_use]
+    pub fn prefix_length(&self) -> u8 {
+        match self {
+            RecordConfig::Full { prefix_length, .. } => {
+                prefix_length.unwrap_or(DEFAULT_PREFIX_LENGTH)
+            },
+            RecordConfig::Name(_) => DEFAULT_PREFIX_LENGTH,
+        }
+    }
+
+    /// Whether the Cloudflare-only `proxied`, `comment` or `tags` are set.
+    fn has_cloudflare_settings(&self) -> bool {
+        matches!(
+            self,
+            RecordConfig::Full { proxied, comment, tags, .. }
+                if proxied.is_some() || comment.is_some() || tags.is_some()
+        )
+    }
+
+    /// `record` with the TTL, proxy status, comment and tags configured here
+    /// applied. Settings that aren't configured keep their current value.
+    #[must_use]
+    pub fn apply_settings(&self, record: &DnsRecord) -> DnsRecord {
+        let mut record = record.clone();
+
+        if let RecordConfig::Full { ttl, proxied, comment, tags, .. } = self {
+            record.ttl = ttl.or(record.ttl);
+            record.proxied = proxied.or(record.proxied);
+            if let Some(comment) = comment {
+                record.comment = Some(comment.clone());
+            }
+            if let Some(tags) = tags {
+                record.tags = Some(tags.clone());
+            }
+        }
+
+        record
+    }
+
+    /// The address this record should point to, given the public address.
+    ///
+    /// With an `interface_id`, the first `prefix_length` bits are taken from
+    /// the public address and the remaining bits from the interface ID.
+    #[must_use]
+    pub fn target_address(&self, public_ip_address: IpAddr) -> IpAddr {
+        match (public_ip_address, self.interface_id()) {
+            (IpAddr::V6(prefix), Some(interface_id)) => {
+                let prefix_mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix_length()))
+                    .unwrap_or(0);
+
+                IpAddr::V6(Ipv6Addr::from(
+                    (u128::from(prefix) & prefix_mask)
+                        | (u128::from(interface_id) & !prefix_mask),
+                ))
+            },
+            _ => public_ip_address,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -114,20 +508,75 @@ mod test {
 
     const EXAMPLE: &str = include_str!("../../test/example.toml");
 
+    #[allow(clippy::too_many_lines)]
     fn get_expected_config() -> ApplicationConfig {
         ApplicationConfig {
-            public_ip_url: "https://example.ip".to_owned(),
-            cloudflare: Some(ProviderConfig {
-                token: "cf_token".to_owned(),
+            public_ip_url: Some("https://example.ip".to_owned()),
+            public_ip_url_v6: Some("https://example6.ip".to_owned()),
+            public_ip: PublicIpConfig {
+                strategy: IpStrategy::Consensus(2),
+                strategy_v6: Some(IpStrategy::Fallback),
+                ipv4: vec![
+                    IpSourceConfig::Url("https://other.example.ip".to_owned()),
+                    IpSourceConfig::Full(IpSource::Http(HttpSource {
+                        format: ResponseFormat::Json("ip".to_owned()),
+                        ..HttpSource::new("https://third.example.ip")
+                    })),
+                ],
+                ..PublicIpConfig::default()
+            },
+            ip_sources: IndexMap::from([("wan2".to_owned(), PublicIpConfig {
+                interface: Some("eth1".to_owned()),
+                ipv4: vec![IpSourceConfig::Url(
+                    "https://example.ip".to_owned(),
+                )],
+                ..PublicIpConfig::default()
+            })]),
+            cloudflare: Some(CloudflareConfig {
+                auth: CloudflareAuth::Token("cf_token".to_owned()),
+                account_id: Some("cf_account".to_owned()),
                 zones: vec![ZoneConfig {
                     name: "example.nl".to_owned(),
                     records: vec![
                         RecordConfig::Full {
                             record_type: DnsRecordType::A,
                             name: "www".to_owned(),
+                            interface_id: None,
+                            prefix_length: None,
+                            ip_source: None,
+                            ttl: Some(1),
+                            proxied: Some(true),
+                            comment: Some("Managed by ryndns".to_owned()),
+                            tags: Some(vec!["env:home".to_owned()]),
                         },
                         RecordConfig::Name("mail".to_owned()),
+                        RecordConfig::Full {
+                            record_type: DnsRecordType::AAAA,
+                            name: "www".to_owned(),
+                            interface_id: None,
+                            prefix_length: None,
+                            ip_source: None,
+                            ttl: None,
+                            proxied: None,
+                            comment: None,
+                            tags: None,
+                        },
+                        RecordConfig::Full {
+                            record_type: DnsRecordType::AAAA,
+                            name: "nas".to_owned(),
+                            interface_id: Some(
+                                "::1234:5678:9abc:def0".parse().unwrap(),
+                            ),
+                            prefix_length: Some(56),
+                            ip_source: Some("wan2".to_owned()),
+                            ttl: None,
+                            proxied: None,
+                            comment: None,
+                            tags: None,
+                        },
                     ],
+                    private_addresses: PrivateAddressPolicy::Refuse,
+                    ip_source: None,
                 }],
             }),
             bunny: Some(ProviderConfig {
@@ -138,9 +587,48 @@ mod test {
                         RecordConfig::Full {
                             record_type: DnsRecordType::A,
                             name: "www".to_owned(),
+                            interface_id: None,
+                            prefix_length: None,
+                            ip_source: None,
+                            ttl: None,
+                            proxied: None,
+                            comment: None,
+                            tags: None,
                         },
                         RecordConfig::Name("mail".to_owned()),
                     ],
+                    private_addresses: PrivateAddressPolicy::Allow,
+                    ip_source: Some("wan2".to_owned()),
+                }],
+            }),
+            hetzner: Some(ProviderConfig {
+                token: "hetzner_token".to_owned(),
+                zones: vec![ZoneConfig {
+                    name: "example.de".to_owned(),
+                    records: vec![RecordConfig::Name("example.de".to_owned())],
+                    private_addresses: PrivateAddressPolicy::Refuse,
+                    ip_source: None,
+                }],
+            }),
+            digitalocean: Some(ProviderConfig {
+                token: "do_token".to_owned(),
+                zones: vec![ZoneConfig {
+                    name: "example.org".to_owned(),
+                    records: vec![RecordConfig::Name("www".to_owned())],
+                    private_addresses: PrivateAddressPolicy::Refuse,
+                    ip_source: None,
+                }],
+            }),
+            route53: Some(Route53Config {
+                access_key: None,
+                profile: Some("ryndns".to_owned()),
+                endpoint: None,
+                wait_for_sync: true,
+                zones: vec![ZoneConfig {
+                    name: "example.net".to_owned(),
+                    records: vec![RecordConfig::Name("home".to_owned())],
+                    private_addresses: PrivateAddressPolicy::Refuse,
+                    ip_source: None,
                 }],
             }),
         }
@@ -152,4 +640,187 @@ mod test {
         assert_eq!(config, get_expected_config());
         Ok(())
     }
+
+    #[test]
+    fn test_cloudflare_auth() {
+        let zones = "zones = []\n";
+
+        let config: CloudflareConfig =
+            toml::from_str(&format!("token = \"t\"\n{zones}")).unwrap();
+        assert_eq!(config.auth(), &CloudflareAuth::Token("t".to_owned()));
+
+        let config: CloudflareConfig = toml::from_str(&format!(
+            "email = \"me@example.com\"\napi_key = \"k\"\n{zones}"
+        ))
+        .unwrap();
+        assert_eq!(config.auth(), &CloudflareAuth::GlobalKey {
+            email: "me@example.com".to_owned(),
+            api_key: "k".to_owned(),
+        });
+
+        for invalid in [
+            "",
+            "token = \"t\"\napi_key = \"k\"\n",
+            "email = \"me@example.com\"\n",
+        ] {
+            assert!(
+                toml::from_str::<CloudflareConfig>(&format!(
+                    "{invalid}{zones}"
+                ))
+                .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_route53_access_key() {
+        let zones = "zones = []\n";
+
+        let config: Route53Config = toml::from_str(&format!(
+            "access_key_id = \"AKID\"\nsecret_access_key = \"s\"\n{zones}"
+        ))
+        .unwrap();
+        assert_eq!(
+            config.access_key(),
+            Some(&AwsAccessKey {
+                access_key_id: "AKID".to_owned(),
+                secret_access_key: "s".to_owned(),
+                session_token: None,
+            })
+        );
+
+        let config: Route53Config = toml::from_str(zones).unwrap();
+        assert_eq!(config.access_key(), None);
+        assert!(!config.wait_for_sync());
+
+        for invalid in [
+            "access_key_id = \"AKID\"\n",
+            "session_token = \"t\"\n",
+            "access_key_id = \"AKID\"\nsecret_access_key = \"s\"\n\
+             profile = \"p\"\n",
+        ] {
+            assert!(
+                toml::from_str::<Route53Config>(&format!("{invalid}{zones}"))
+                    .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_target_address() -> Result<()> {
+        let config: ApplicationConfig = toml::from_str(EXAMPLE)?;
+        let zone = &config.cloudflare.unwrap().zones[0];
+        let public_ip_address: IpAddr = "2001:db8:aa:bb01::1".parse()?;
+
+        let www = zone.record_config("www.example.nl", DnsRecordType::AAAA);
+        assert_eq!(
+            www.unwrap().target_address(public_ip_address),
+            public_ip_address
+        );
+
+        let nas = zone.record_config("nas.example.nl", DnsRecordType::AAAA);
+        assert_eq!(
+            nas.unwrap().target_address(public_ip_address),
+            "2001:db8:aa:bb00:1234:5678:9abc:def0".parse::<IpAddr>()?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_settings() -> Result<()> {
+        let config: ApplicationConfig = toml::from_str(EXAMPLE)?;
+        let zone = &config.cloudflare.unwrap().zones[0];
+        let record = DnsRecord {
+            id: "1".to_owned(),
+            name: "www.example.nl".to_owned(),
+            record_type: DnsRecordType::A,
+            raw_type: "A".to_owned(),
+            content: "198.51.100.1".to_owned(),
+            ttl: Some(3600),
+            proxied: Some(false),
+            comment: None,
+            tags: Some(vec!["env:old".to_owned()]),
+            stored: None,
+        };
+
+        let www = zone.record_config(&record.name, record.record_type).unwrap();
+        let updated = www.apply_settings(&record);
+        assert_eq!(updated.ttl, Some(1));
+        assert_eq!(updated.proxied, Some(true));
+        assert_eq!(updated.comment.as_deref(), Some("Managed by ryndns"));
+        assert_eq!(updated.tags, Some(vec!["env:home".to_owned()]));
+
+        let mail = zone.record_config("mail.example.nl", DnsRecordType::A);
+        let unchanged = mail.unwrap().apply_settings(&record);
+        assert_eq!(unchanged.ttl, Some(3600));
+        assert_eq!(unchanged.proxied, Some(false));
+        assert_eq!(unchanged.tags, Some(vec!["env:old".to_owned()]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_public_ip_sources() -> Result<()> {
+        let config: ApplicationConfig = toml::from_str(EXAMPLE)?;
+        let public_ip = config.public_ip();
+
+        assert_eq!(
+            public_ip.strategy(IpFamily::Ipv4),
+            IpStrategy::Consensus(2)
+        );
+        assert_eq!(public_ip.strategy(IpFamily::Ipv6), IpStrategy::Fallback);
+
+        let urls: Vec<_> = public_ip
+            .sources(IpFamily::Ipv4)
+            .iter()
+            .map(|s| s.source().to_string())
+            .collect();
+        assert_eq!(urls, [
+            "https://example.ip",
+            "https://other.example.ip",
+            "https://third.example.ip"
+        ]);
+
+        assert_eq!(public_ip.sources(IpFamily::Ipv6).len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate() -> Result<()> {
+        let config: ApplicationConfig = toml::from_str(EXAMPLE)?;
+        assert_eq!(config.validate(), Ok(()));
+
+        // `public_ip_url_v6` is the only IPv6 source, so it can't reach a
+        // consensus of 2.
+        let config: ApplicationConfig = toml::from_str(
+            &EXAMPLE.replace("strategy_v6 = \"fallback\"\n", ""),
+        )?;
+        assert!(config.validate().is_err());
+
+        let config: ApplicationConfig = toml::from_str(
+            &EXAMPLE.replace("[ip_sources.wan2]", "[ip_sources.default]"),
+        )?;
+        assert!(config.validate().is_err());
+
+        for (from, to) in [
+            (r#"type = "AAAA", name = "nas""#, r#"type = "TXT", name = "nas""#),
+            (r#"type = "AAAA", name = "nas""#, r#"type = "A", name = "nas""#),
+            ("prefix_length = 56", "prefix_length = 129"),
+            (
+                r#"records = ["www"]"#,
+                r#"records = [{ name = "www", tags = [] }]"#,
+            ),
+        ] {
+            let config: ApplicationConfig =
+                toml::from_str(&EXAMPLE.replace(from, to))?;
+            assert!(config.validate().is_err(), "{to}");
+        }
+
+        // Router sources are queried from the default route.
+        let config: ApplicationConfig = toml::from_str(&EXAMPLE.replace(
+            r#"ipv4 = ["https://example.ip"]"#,
+            r#"ipv4 = [{ type = "upnp" }]"#,
+        ))?;
+        assert!(config.validate().is_err());
+        Ok(())
+    }
 }
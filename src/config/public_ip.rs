@@ -0,0 +1,337 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
+
+use indexmap::IndexMap;
+use serde::Deserialize;
+use strum_macros::Display;
+
+use crate::ip_family::IpFamily;
+
+const DEFAULT_CONNECT_TIMEOUT: u64 = 5;
+const DEFAULT_TIMEOUT: u64 = 10;
+const DEFAULT_RETRIES: u32 = 2;
+const DEFAULT_RETRY_DELAY: u64 = 1;
+
+/// How and where to look up the public IP addresses.
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct PublicIpConfig {
+    #[serde(default)]
+    pub(super) strategy: IpStrategy,
+    /// The strategy for IPv6 sources, if it differs from `strategy`.
+    pub(super) strategy_v6: Option<IpStrategy>,
+    #[serde(default)]
+    pub(super) ipv4: Vec<IpSourceConfig>,
+    #[serde(default)]
+    pub(super) ipv6: Vec<IpSourceConfig>,
+    /// Seconds to wait for a connection to an HTTP source.
+    pub(super) connect_timeout: Option<u64>,
+    /// Seconds to wait for a source to answer, including retries by the
+    /// protocol itself.
+    pub(super) timeout: Option<u64>,
+    /// How often to retry a source after a transient error.
+    pub(super) retries: Option<u32>,
+    /// Seconds to wait before the first retry, doubled for every next retry.
+    pub(super) retry_delay: Option<u64>,
+    /// Local IPv4 address to send lookups from.
+    pub(super) local_address: Option<Ipv4Addr>,
+    /// Local IPv6 address to send lookups from.
+    pub(super) local_address_v6: Option<Ipv6Addr>,
+    /// Network interface to send lookups through.
+    pub(super) interface: Option<String>,
+}
+
+impl PublicIpConfig {
+    #[must_use]
+    pub fn strategy(&self, family: IpFamily) -> IpStrategy {
+        match family {
+            IpFamily::Ipv4 => self.strategy,
+            IpFamily::Ipv6 => self.strategy_v6.unwrap_or(self.strategy),
+        }
+    }
+
+    #[must_use]
+    pub fn sources(&self, family: IpFamily) -> &[IpSourceConfig] {
+        match family {
+            IpFamily::Ipv4 => &self.ipv4,
+            IpFamily::Ipv6 => &self.ipv6,
+        }
+    }
+
+    #[must_use]
+    pub fn connect_timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.connect_timeout.unwrap_or(DEFAULT_CONNECT_TIMEOUT),
+        )
+    }
+
+    #[must_use]
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout.unwrap_or(DEFAULT_TIMEOUT))
+    }
+
+    #[must_use]
+    pub fn retries(&self) -> u32 {
+        self.retries.unwrap_or(DEFAULT_RETRIES)
+    }
+
+    #[must_use]
+    pub fn retry_delay(&self) -> Duration {
+        Duration::from_secs(self.retry_delay.unwrap_or(DEFAULT_RETRY_DELAY))
+    }
+
+    /// The local address lookups of `family` are sent from. The unspecified
+    /// address lets the system choose.
+    #[must_use]
+    pub fn local_address(&self, family: IpFamily) -> IpAddr {
+        match family {
+            IpFamily::Ipv4 => self.local_address.map(IpAddr::V4),
+            IpFamily::Ipv6 => self.local_address_v6.map(IpAddr::V6),
+        }
+        .unwrap_or(family.unspecified_address())
+    }
+
+    #[must_use]
+    pub fn interface(&self) -> Option<&str> {
+        self.interface.as_deref()
+    }
+
+    /// Checks that the sources of each family can reach the quorum of its
+    /// strategy, and that the sources can be bound to `interface` and
+    /// `local_address`.
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        for family in [IpFamily::Ipv4, IpFamily::Ipv6] {
+            let sources = self.sources(family).len();
+
+            if let IpStrategy::Consensus(quorum) = self.strategy(family)
+                && sources > 0
+                && (quorum == 0 || quorum > sources)
+            {
+                return Err(format!(
+                    "a consensus of {quorum} is impossible with {sources} \
+                     {family} sources"
+                ));
+            }
+        }
+
+        let bound = self.interface.is_some()
+            || self.local_address.is_some()
+            || self.local_address_v6.is_some();
+
+        let unbindable = self.ipv4.iter().chain(&self.ipv6).find(|s| {
+            matches!(
+                s,
+                IpSourceConfig::Full(
+                    IpSource::Interface { .. }
+                        | IpSource::Upnp { .. }
+                        | IpSource::NatPmp { .. }
+                        | IpSource::Pcp { .. }
+                )
+            )
+        });
+
+        if bound && let Some(source) = unbindable {
+            return Err(format!(
+                "`interface` and `local_address` only apply to HTTP, DNS and \
+                 STUN sources, not to {}",
+                source.source()
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn prepend_url(&mut self, family: IpFamily, url: &str) {
+        let sources = match family {
+            IpFamily::Ipv4 => &mut self.ipv4,
+            IpFamily::Ipv6 => &mut self.ipv6,
+        };
+
+        sources.insert(0, IpSourceConfig::Url(url.to_owned()));
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq, Eq, Default, Copy, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum IpStrategy {
+    /// Use the first source, in order, that returns an address.
+    #[default]
+    Fallback,
+    /// Query every source and require at least this many to return the same
+    /// address.
+    Consensus(usize),
+}
+
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(untagged)]
+pub enum IpSourceConfig {
+    Full(IpSource),
+    Url(String),
+}
+
+impl IpSourceConfig {
+    #[must_use]
+    pub fn source(&self) -> IpSource {
+        match self {
+            IpSourceConfig::Full(source) => source.clone(),
+            IpSourceConfig::Url(url) => IpSource::Http(HttpSource::new(url)),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum IpSource {
+    /// A web service that responds with the address of the client.
+    Http(HttpSource),
+    /// An address assigned to a local network interface.
+    Interface {
+        /// Only consider addresses on this interface.
+        interface: Option<String>,
+        #[serde(default)]
+        scope: AddressScope,
+    },
+    /// A DNS resolver that answers with the address of the client.
+    Dns {
+        /// Host name or address of the resolver, with an optional port.
+        resolver: String,
+        name: String,
+        #[serde(default)]
+        query: DnsQueryType,
+    },
+    /// A STUN server, which reports the address a binding request came from.
+    Stun {
+        /// Host name or address of the server, with an optional port.
+        server: String,
+    },
+    /// The WAN address of a `UPnP` Internet Gateway Device.
+    Upnp {
+        /// URL of the device description. Discovered with SSDP if omitted.
+        location: Option<String>,
+    },
+    /// The external address reported by a NAT-PMP gateway.
+    #[serde(rename = "natpmp")]
+    NatPmp {
+        /// Address of the gateway. Defaults to the default gateway.
+        gateway: Option<String>,
+    },
+    /// The external address of a mapping requested from a PCP server.
+    Pcp {
+        /// Address of the server. Defaults to the default gateway.
+        gateway: Option<String>,
+    },
+}
+
+#[derive(Deserialize, Debug, PartialEq, Eq, Default, Copy, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum DnsQueryType {
+    /// Query A records for IPv4 and AAAA records for IPv6.
+    #[default]
+    Address,
+    /// Query TXT records containing the address.
+    Txt,
+}
+
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct HttpSource {
+    pub url: String,
+    #[serde(default)]
+    pub method: HttpMethod,
+    #[serde(default)]
+    pub headers: IndexMap<String, String>,
+    pub auth: Option<HttpAuth>,
+    #[serde(default)]
+    pub format: ResponseFormat,
+}
+
+impl HttpSource {
+    #[must_use]
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_owned(),
+            method: HttpMethod::default(),
+            headers: IndexMap::new(),
+            auth: None,
+            format: ResponseFormat::default(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq, Eq, Default, Copy, Clone)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HttpMethod {
+    #[default]
+    Get,
+    Post,
+}
+
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum HttpAuth {
+    Bearer(String),
+    Basic { username: String, password: Option<String> },
+}
+
+/// Where to find the address in the response body.
+#[derive(Deserialize, Debug, PartialEq, Eq, Default, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// The whole body is the address.
+    #[default]
+    Text,
+    /// A JSON body, with the address at a dot-separated field path.
+    Json(String),
+    /// Lines of `key=value` pairs, with the address at the given key.
+    KeyValue(String),
+}
+
+/// The reach of an address, used to pick one from a network interface.
+#[derive(Deserialize, Debug, PartialEq, Eq, Default, Copy, Clone, Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum AddressScope {
+    /// Loopback addresses.
+    Host,
+    /// Link-local addresses.
+    Link,
+    /// Private, shared (CGNAT) and unique local addresses.
+    Site,
+    /// Publicly routable addresses.
+    #[default]
+    Global,
+}
+
+impl std::fmt::Display for IpSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IpSource::Http(http) => write!(f, "{}", http.url),
+            IpSource::Interface { interface, scope } => {
+                write!(
+                    f,
+                    "{scope} address on {}",
+                    interface.as_deref().unwrap_or("any interface")
+                )
+            },
+            IpSource::Dns { resolver, name, .. } => {
+                write!(f, "{name} via {resolver}")
+            },
+            IpSource::Stun { server } => write!(f, "stun:{server}"),
+            IpSource::Upnp { location } => {
+                write!(f, "UPnP {}", location.as_deref().unwrap_or("gateway"))
+            },
+            IpSource::NatPmp { gateway } => {
+                write!(
+                    f,
+                    "NAT-PMP {}",
+                    gateway.as_deref().unwrap_or("default gateway")
+                )
+            },
+            IpSource::Pcp { gateway } => {
+                write!(
+                    f,
+                    "PCP {}",
+                    gateway.as_deref().unwrap_or("default gateway")
+                )
+            },
+        }
+    }
+}
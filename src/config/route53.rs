@@ -0,0 +1,101 @@
+use serde::Deserialize;
+
+use super::ZoneConfig;
+
+/// The `[route53]` section. Without an access key, credentials are taken
+/// from the `AWS_*` environment variables or the shared credentials file.
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(try_from = "RawRoute53Config")]
+pub struct Route53Config {
+    pub(super) access_key: Option<AwsAccessKey>,
+    pub(super) profile: Option<String>,
+    pub(super) endpoint: Option<String>,
+    pub(super) wait_for_sync: bool,
+    pub(super) zones: Vec<ZoneConfig>,
+}
+
+impl Route53Config {
+    #[must_use]
+    pub fn access_key(&self) -> Option<&AwsAccessKey> {
+        self.access_key.as_ref()
+    }
+
+    /// The profile to read from the shared credentials file.
+    #[must_use]
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// The API endpoint to use instead of Route 53's, e.g. for a local
+    /// stand-in.
+    #[must_use]
+    pub fn endpoint(&self) -> Option<&str> {
+        self.endpoint.as_deref()
+    }
+
+    /// Whether to wait for changes to reach every Route 53 name server.
+    #[must_use]
+    pub fn wait_for_sync(&self) -> bool {
+        self.wait_for_sync
+    }
+
+    #[must_use]
+    pub fn zones(&self) -> &[ZoneConfig] {
+        &self.zones
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AwsAccessKey {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawRoute53Config {
+    access_key_id: Option<String>,
+    secret_access_key: Option<String>,
+    session_token: Option<String>,
+    profile: Option<String>,
+    endpoint: Option<String>,
+    #[serde(default)]
+    wait_for_sync: bool,
+    zones: Vec<ZoneConfig>,
+}
+
+impl TryFrom<RawRoute53Config> for Route53Config {
+    type Error = String;
+
+    fn try_from(raw: RawRoute53Config) -> Result<Self, Self::Error> {
+        let access_key = match (raw.access_key_id, raw.secret_access_key) {
+            (Some(access_key_id), Some(secret_access_key)) => {
+                if raw.profile.is_some() {
+                    return Err("set either an access key or `profile`, \
+                                not both"
+                        .to_owned());
+                }
+
+                Some(AwsAccessKey {
+                    access_key_id,
+                    secret_access_key,
+                    session_token: raw.session_token,
+                })
+            },
+            (None, None) if raw.session_token.is_none() => None,
+            _ => {
+                return Err("`access_key_id` and `secret_access_key` must be \
+                            set together"
+                    .to_owned());
+            },
+        };
+
+        Ok(Self {
+            access_key,
+            profile: raw.profile,
+            endpoint: raw.endpoint,
+            wait_for_sync: raw.wait_for_sync,
+            zones: raw.zones,
+        })
+    }
+}
@@ -0,0 +1,45 @@
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+
+use super::{DIGITALOCEAN_API_URL, list_all};
+
+#[derive(Deserialize, Debug)]
+pub struct DigitalOceanDomain {
+    pub name: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct GetDomainResponse {
+    domain: DigitalOceanDomain,
+}
+
+pub async fn list_domains(client: &Client) -> Result<Vec<DigitalOceanDomain>> {
+    let url = format!("{DIGITALOCEAN_API_URL}/domains?per_page=200");
+
+    list_all(client, url, "list_domains").await
+}
+
+/// The domain named `name`, if there is one.
+pub async fn get_domain(
+    client: &Client,
+    name: &str,
+) -> Result<Option<DigitalOceanDomain>> {
+    let response = client
+        .get(format!("{DIGITALOCEAN_API_URL}/domains/{name}"))
+        .send()
+        .await?;
+
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+
+    let response: GetDomainResponse = response
+        .error_for_status()
+        .map_err(|e| eyre!("DigitalOcean get_domain failed: {e}"))?
+        .json()
+        .await?;
+
+    Ok(Some(response.domain))
+}
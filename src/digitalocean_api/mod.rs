@@ -0,0 +1,165 @@
+pub mod domain;
+pub mod record;
+
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use reqwest::Client;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+
+const DIGITALOCEAN_API_URL: &str = "https://api.digitalocean.com/v2";
+
+pub fn build_digitalocean_client(token: &str) -> Result<Client> {
+    use reqwest::header::HeaderMap;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "Content-Type",
+        "application/json"
+            .parse()
+            .map_err(|_| eyre!("Invalid Content-Type header"))?,
+    );
+    headers.insert(
+        "Authorization",
+        format!("Bearer {token}")
+            .parse()
+            .map_err(|_| eyre!("Invalid Authorization header"))?,
+    );
+    Ok(Client::builder().default_headers(headers).use_rustls_tls().build()?)
+}
+
+/// Links to other pages of a list response.
+#[derive(Deserialize, Debug, Default)]
+struct Links {
+    #[serde(default)]
+    pages: Pages,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct Pages {
+    /// The URL of the next page, absent on the last page.
+    next: Option<String>,
+}
+
+/// A page of a list response, with the items under `domains` or
+/// `domain_records`.
+#[derive(Deserialize, Debug)]
+struct Page<T> {
+    #[serde(alias = "domains", alias = "domain_records")]
+    items: Vec<T>,
+    #[serde(default)]
+    links: Links,
+}
+
+/// Every item of a list, from the page at `url` and those following it.
+async fn list_all<T: DeserializeOwned>(
+    client: &Client,
+    mut url: String,
+    operation: &str,
+) -> Result<Vec<T>> {
+    let mut all_items = Vec::new();
+
+    loop {
+        let page: Page<T> = client
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()
+            .map_err(|e| eyre!("DigitalOcean {operation} failed: {e}"))?
+            .json()
+            .await?;
+
+        all_items.extend(page.items);
+
+        match page.links.pages.next {
+            Some(next) => url = next,
+            None => break,
+        }
+    }
+
+    Ok(all_items)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    use super::*;
+    use crate::digitalocean_api::domain::DigitalOceanDomain;
+
+    /// A local stand-in for the API, serving two pages of domains and
+    /// returning its endpoint and the paths it receives.
+    fn stand_in() -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let paths = Arc::new(Mutex::new(Vec::new()));
+        let received = Arc::clone(&paths);
+        let next = format!("{endpoint}/domains?page=2&per_page=1");
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(&stream);
+
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let path =
+                    request_line.split_whitespace().nth(1).unwrap().to_owned();
+
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                }
+
+                let response = if path.contains("page=2") {
+                    r#"{ "domains": [{ "name": "example.org" }],
+                        "links": {} }"#
+                        .to_owned()
+                } else {
+                    format!(
+                        r#"{{ "domains": [{{ "name": "example.com" }}],
+                            "links": {{ "pages": {{ "next": "{next}" }} }} }}"#
+                    )
+                };
+
+                received.lock().unwrap().push(path);
+
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\
+                     Connection: close\r\n\r\n{response}",
+                    response.len()
+                )
+                .unwrap();
+            }
+        });
+
+        (endpoint, paths)
+    }
+
+    #[tokio::test]
+    async fn test_list_all() -> Result<()> {
+        let (endpoint, paths) = stand_in();
+
+        let domains: Vec<DigitalOceanDomain> = list_all(
+            &Client::new(),
+            format!("{endpoint}/domains?per_page=1"),
+            "list_domains",
+        )
+        .await?;
+
+        let names: Vec<_> = domains.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, ["example.com", "example.org"]);
+        assert_eq!(*paths.lock().unwrap(), [
+            "/domains?per_page=1",
+            "/domains?page=2&per_page=1"
+        ]);
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use super::{DIGITALOCEAN_API_URL, list_all};
+
+#[derive(Deserialize, Debug)]
+pub struct DigitalOceanRecord {
+    pub id: i64,
+    #[serde(rename = "type")]
+    pub record_type: String,
+    /// Relative to the domain, with `@` for the apex.
+    pub name: String,
+    pub data: String,
+    pub ttl: Option<u32>,
+}
+
+pub async fn list_records(
+    client: &Client,
+    domain: &str,
+) -> Result<Vec<DigitalOceanRecord>> {
+    let url =
+        format!("{DIGITALOCEAN_API_URL}/domains/{domain}/records?per_page=200");
+
+    list_all(client, url, "list_records").await
+}
+
+/// The attributes to change. Those left out keep their value.
+#[derive(Serialize, Debug)]
+pub struct PatchRecordRequest<'a> {
+    #[serde(rename = "type")]
+    pub record_type: &'a str,
+    pub data: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u32>,
+}
+
+pub async fn patch_record(
+    client: &Client,
+    domain: &str,
+    record_id: &str,
+    request: &PatchRecordRequest<'_>,
+) -> Result<()> {
+    client
+        .patch(format!(
+            "{DIGITALOCEAN_API_URL}/domains/{domain}/records/{record_id}"
+        ))
+        .json(request)
+        .send()
+        .await?
+        .error_for_status()
+        .map_err(|e| eyre!("DigitalOcean patch_record failed: {e}"))?;
+
+    Ok(())
+}
@@ -0,0 +1,46 @@
+pub mod record;
+pub mod zone;
+
+use serde::Deserialize;
+
+const HETZNER_API_URL: &str = "https://dns.hetzner.com/api/v1";
+
+pub fn build_hetzner_client(
+    token: &str,
+) -> color_eyre::Result<reqwest::Client> {
+    use color_eyre::eyre::eyre;
+    use reqwest::header::HeaderMap;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "Content-Type",
+        "application/json"
+            .parse()
+            .map_err(|_| eyre!("Invalid Content-Type header"))?,
+    );
+    headers.insert(
+        "Auth-API-Token",
+        token.parse().map_err(|_| eyre!("Invalid Auth-API-Token header"))?,
+    );
+    Ok(reqwest::Client::builder()
+        .default_headers(headers)
+        .use_rustls_tls()
+        .build()?)
+}
+
+#[derive(Deserialize, Debug)]
+struct Meta {
+    pagination: Pagination,
+}
+
+#[derive(Deserialize, Debug)]
+struct Pagination {
+    page: u32,
+    last_page: u32,
+}
+
+impl Meta {
+    fn has_more_pages(&self) -> bool {
+        self.pagination.page < self.pagination.last_page
+    }
+}
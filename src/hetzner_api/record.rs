@@ -0,0 +1,104 @@
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use super::{HETZNER_API_URL, Meta};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct HetznerRecord {
+    #[serde(skip_serializing)]
+    pub id: String,
+    pub zone_id: String,
+    #[serde(rename = "type")]
+    pub record_type: String,
+    /// Relative to the zone, with `@` for the apex.
+    pub name: String,
+    pub value: String,
+    /// Omitted when the record uses the default TTL of the zone.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u32>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ListRecordsResponse {
+    records: Vec<HetznerRecord>,
+    meta: Option<Meta>,
+}
+
+pub async fn list_records(
+    client: &Client,
+    zone_id: &str,
+) -> Result<Vec<HetznerRecord>> {
+    let mut all_records = Vec::new();
+    let mut page = 1u32;
+
+    loop {
+        let response: ListRecordsResponse = client
+            .get(format!(
+                "{HETZNER_API_URL}/records?zone_id={zone_id}&page={page}&per_page=100"
+            ))
+            .send()
+            .await?
+            .error_for_status()
+            .map_err(|e| eyre!("Hetzner list_records failed: {e}"))?
+            .json()
+            .await?;
+
+        all_records.extend(response.records);
+
+        if !response.meta.is_some_and(|meta| meta.has_more_pages()) {
+            break;
+        }
+        page += 1;
+    }
+
+    Ok(all_records)
+}
+
+/// Replaces the record with ID `record.id` with `record`.
+pub async fn update_record(
+    client: &Client,
+    record: &HetznerRecord,
+) -> Result<()> {
+    client
+        .put(format!("{HETZNER_API_URL}/records/{}", record.id))
+        .json(record)
+        .send()
+        .await?
+        .error_for_status()
+        .map_err(|e| eyre!("Hetzner update_record failed: {e}"))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_update_body() -> Result<()> {
+        let record: HetznerRecord = serde_json::from_value(json!({
+            "id": "r1",
+            "zone_id": "z1",
+            "type": "A",
+            "name": "@",
+            "value": "198.51.100.1",
+            "created": "2024-01-01 00:00:00 +0000 UTC",
+            "modified": "2024-01-01 00:00:00 +0000 UTC",
+        }))?;
+
+        assert_eq!(
+            serde_json::to_value(&record)?,
+            json!({
+                "zone_id": "z1",
+                "type": "A",
+                "name": "@",
+                "value": "198.51.100.1",
+            })
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use reqwest::{Client, Url};
+use serde::Deserialize;
+
+use super::{HETZNER_API_URL, Meta};
+
+#[derive(Deserialize, Debug)]
+pub struct HetznerZone {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct ListZonesResponse {
+    zones: Vec<HetznerZone>,
+    meta: Option<Meta>,
+}
+
+/// Lists the zones, or only the zone named `name`.
+pub async fn list_zones(
+    client: &Client,
+    name: Option<&str>,
+) -> Result<Vec<HetznerZone>> {
+    let mut all_zones = Vec::new();
+    let mut page = 1u32;
+
+    loop {
+        let mut url = Url::parse(&format!("{HETZNER_API_URL}/zones"))?;
+        url.query_pairs_mut()
+            .append_pair("page", &page.to_string())
+            .append_pair("per_page", "100");
+        if let Some(name) = name {
+            url.query_pairs_mut().append_pair("name", name);
+        }
+
+        let response = client.get(url).send().await?;
+
+        // Looking up a zone by a name that doesn't exist is a 404.
+        if name.is_some() && response.status() == reqwest::StatusCode::NOT_FOUND
+        {
+            return Ok(Vec::new());
+        }
+
+        let response: ListZonesResponse = response
+            .error_for_status()
+            .map_err(|e| eyre!("Hetzner list_zones failed: {e}"))?
+            .json()
+            .await?;
+
+        all_zones.extend(response.zones);
+
+        if !response.meta.is_some_and(|meta| meta.has_more_pages()) {
+            break;
+        }
+        page += 1;
+    }
+
+    Ok(all_zones)
+}
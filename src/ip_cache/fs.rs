@@ -5,6 +5,9 @@ use color_eyre::Result;
 use color_eyre::eyre::eyre;
 use indexmap::IndexMap;
 
+use crate::config::DEFAULT_IP_SOURCE;
+use crate::ip_family::IpFamily;
+
 static DELIMITER: &str = ";";
 
 use super::IpCache;
@@ -19,11 +22,31 @@ impl IpCacheReader {
             let mut cache = IndexMap::new();
 
             for line in body.lines() {
-                let (key, value) = line.split_once(DELIMITER).ok_or(eyre!(
-                    "Line should contain two values, separated by {DELIMITER}"
-                ))?;
+                // Addresses of the default IP source are stored without its
+                // name, as they were before named sources existed.
+                let (key, ip_source, value) = match line
+                    .split(DELIMITER)
+                    .collect::<Vec<_>>()[..]
+                {
+                    [key, value] => (key, DEFAULT_IP_SOURCE, value),
+                    [key, ip_source, value] => (key, ip_source, value),
+                    _ => {
+                        return Err(eyre!(
+                            "Line should contain two or three values, separated by {DELIMITER}"
+                        ));
+                    },
+                };
+
+                let ip_address = value.parse()?;
 
-                cache.insert(key.to_owned(), value.parse()?);
+                cache.insert(
+                    (
+                        key.to_owned(),
+                        ip_source.to_owned(),
+                        IpFamily::of(ip_address),
+                    ),
+                    ip_address,
+                );
             }
 
             Ok(IpCache::new(cache))
@@ -41,8 +64,16 @@ impl IpCacheWriter {
     pub fn save(&self, ip_cache: &IpCache, path: &Utf8Path) -> Result<()> {
         let body = ip_cache.into_iter().fold(
             String::new(),
-            |mut acc, (key, value)| {
-                writeln!(acc, "{key}{DELIMITER}{value}").unwrap();
+            |mut acc, ((key, ip_source, _), value)| {
+                if ip_source == DEFAULT_IP_SOURCE {
+                    writeln!(acc, "{key}{DELIMITER}{value}").unwrap();
+                } else {
+                    writeln!(
+                        acc,
+                        "{key}{DELIMITER}{ip_source}{DELIMITER}{value}"
+                    )
+                    .unwrap();
+                }
                 acc
             },
         );
@@ -1,44 +1,57 @@
 mod fs;
 
-use std::net::Ipv4Addr;
+use std::net::IpAddr;
 
 pub use fs::{IpCacheReader, IpCacheWriter};
 use indexmap::IndexMap;
 
-/// Caches the latest IP for a give zone ID.
+use crate::ip_family::IpFamily;
+
+/// Identifies a cached address: the zone ID, the name of the IP source and
+/// the address family.
+pub type IpCacheKey = (String, String, IpFamily);
+
+/// Caches the latest IPv4 and IPv6 address of each IP source for a given zone
+/// ID.
 #[derive(Debug, Default, Clone)]
 pub struct IpCache {
-    cache: IndexMap<String, Ipv4Addr>,
+    cache: IndexMap<IpCacheKey, IpAddr>,
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum IpCacheResult {
     New,
     Unchanged,
-    Changed { previous_ip_address: Ipv4Addr },
+    Changed { previous_ip_address: IpAddr },
 }
 
 impl IpCache {
     #[must_use]
-    pub fn new(cache: IndexMap<String, Ipv4Addr>) -> Self {
+    pub fn new(cache: IndexMap<IpCacheKey, IpAddr>) -> Self {
         Self { cache }
     }
 
     pub fn handle_ip(
         &mut self,
         zone_id: &str,
-        ip_address: Ipv4Addr,
+        ip_source: &str,
+        ip_address: IpAddr,
     ) -> IpCacheResult {
+        let key = (
+            zone_id.to_owned(),
+            ip_source.to_owned(),
+            IpFamily::of(ip_address),
+        );
+
         let cached = self
             .cache
-            .get(zone_id)
+            .get(&key)
             .is_some_and(|saved_ip| *saved_ip == ip_address);
 
         if cached {
             IpCacheResult::Unchanged
         } else {
-            let previous_ip_address =
-                self.cache.insert(zone_id.to_owned(), ip_address);
+            let previous_ip_address = self.cache.insert(key, ip_address);
 
             if let Some(previous_ip_address) = previous_ip_address {
                 IpCacheResult::Changed { previous_ip_address }
@@ -49,14 +62,14 @@ impl IpCache {
     }
 
     #[must_use]
-    pub fn iter(&self) -> indexmap::map::Iter<'_, String, Ipv4Addr> {
+    pub fn iter(&self) -> indexmap::map::Iter<'_, IpCacheKey, IpAddr> {
         self.cache.iter()
     }
 }
 
 impl<'a> IntoIterator for &'a IpCache {
-    type IntoIter = indexmap::map::Iter<'a, String, Ipv4Addr>;
-    type Item = (&'a String, &'a Ipv4Addr);
+    type IntoIter = indexmap::map::Iter<'a, IpCacheKey, IpAddr>;
+    type Item = (&'a IpCacheKey, &'a IpAddr);
 
     fn into_iter(self) -> Self::IntoIter {
         self.iter()
@@ -0,0 +1,57 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use serde::{Deserialize, Serialize};
+use strum_macros::Display;
+
+use crate::provider::DnsRecordType;
+
+/// The address family of an IP address, and of the records that hold it.
+#[derive(
+    Deserialize,
+    Serialize,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Copy,
+    Clone,
+    Display,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum IpFamily {
+    #[strum(to_string = "IPv4")]
+    Ipv4,
+    #[strum(to_string = "IPv6")]
+    Ipv6,
+}
+
+impl IpFamily {
+    #[must_use]
+    pub fn of(ip_address: IpAddr) -> Self {
+        match ip_address {
+            IpAddr::V4(_) => Self::Ipv4,
+            IpAddr::V6(_) => Self::Ipv6,
+        }
+    }
+
+    #[must_use]
+    pub fn unspecified_address(self) -> IpAddr {
+        match self {
+            Self::Ipv4 => Ipv4Addr::UNSPECIFIED.into(),
+            Self::Ipv6 => Ipv6Addr::UNSPECIFIED.into(),
+        }
+    }
+
+    /// The family of the address a record of `record_type` points to, or
+    /// `None` if records of that type don't hold an address.
+    #[must_use]
+    pub fn for_record_type(record_type: DnsRecordType) -> Option<Self> {
+        match record_type {
+            DnsRecordType::A => Some(Self::Ipv4),
+            DnsRecordType::AAAA => Some(Self::Ipv6),
+            _ => None,
+        }
+    }
+}
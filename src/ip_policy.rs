@@ -0,0 +1,215 @@
+//! Guards against publishing addresses that can't be reached from the
+//! internet, like those handed out behind a captive portal or CGNAT.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use strum_macros::Display;
+use tracing::warn;
+
+use crate::config::{AddressScope, PrivateAddressPolicy};
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Display)]
+pub enum AddressClass {
+    #[strum(to_string = "public")]
+    Public,
+    #[strum(to_string = "private")]
+    Private,
+    #[strum(to_string = "shared (CGNAT)")]
+    Shared,
+    #[strum(to_string = "unique local")]
+    UniqueLocal,
+    #[strum(to_string = "link-local")]
+    LinkLocal,
+    #[strum(to_string = "loopback")]
+    Loopback,
+    #[strum(to_string = "documentation")]
+    Documentation,
+    #[strum(to_string = "unspecified")]
+    Unspecified,
+    #[strum(to_string = "multicast")]
+    Multicast,
+    #[strum(to_string = "reserved")]
+    Reserved,
+}
+
+impl AddressClass {
+    #[must_use]
+    pub fn of(ip_address: IpAddr) -> Self {
+        match ip_address {
+            IpAddr::V4(ip) => Self::of_ipv4(ip),
+            IpAddr::V6(ip) => Self::of_ipv6(ip),
+        }
+    }
+
+    fn of_ipv4(ip: Ipv4Addr) -> Self {
+        let [a, b, c, _] = ip.octets();
+
+        match (a, b, c) {
+            (0, ..) => Self::Unspecified,
+            (10, ..) | (192, 168, _) => Self::Private,
+            (172, b, _) if b & 0xf0 == 16 => Self::Private,
+            (100, b, _) if b & 0xc0 == 64 => Self::Shared,
+            (127, ..) => Self::Loopback,
+            (169, 254, _) => Self::LinkLocal,
+            (192, 0, 2) | (198, 51, 100) | (203, 0, 113) => Self::Documentation,
+            (198, b, _) if b & 0xfe == 18 => Self::Reserved,
+            (192, 0, 0) | (240.., ..) => Self::Reserved,
+            (224..=239, ..) => Self::Multicast,
+            _ => Self::Public,
+        }
+    }
+
+    fn of_ipv6(ip: Ipv6Addr) -> Self {
+        if let Some(ip) = ip.to_ipv4_mapped() {
+            return Self::of_ipv4(ip);
+        }
+
+        let [a, b, ..] = ip.segments();
+
+        if ip.is_unspecified() {
+            Self::Unspecified
+        } else if ip.is_loopback() {
+            Self::Loopback
+        } else if ip.is_multicast() {
+            Self::Multicast
+        } else if ip.is_unicast_link_local() {
+            Self::LinkLocal
+        } else if ip.is_unique_local() {
+            Self::UniqueLocal
+        } else if (a, b) == (0x2001, 0x0db8) || (a == 0x3fff && b & 0xf000 == 0)
+        {
+            Self::Documentation
+        } else if a & 0xe000 == 0x2000 {
+            // Global unicast is 2000::/3, the rest is not assigned.
+            Self::Public
+        } else {
+            Self::Reserved
+        }
+    }
+
+    /// Whether this class can be published when explicitly allowed, as
+    /// opposed to addresses that are never useful in a DNS record.
+    #[must_use]
+    pub fn is_private(self) -> bool {
+        matches!(
+            self,
+            Self::Private
+                | Self::Shared
+                | Self::UniqueLocal
+                | Self::LinkLocal
+                | Self::Loopback
+        )
+    }
+
+    /// The scope of an interface address of this class. Addresses that
+    /// aren't private are taken to be reachable from anywhere.
+    #[must_use]
+    pub fn scope(self) -> AddressScope {
+        match self {
+            Self::Loopback | Self::Unspecified => AddressScope::Host,
+            Self::LinkLocal => AddressScope::Link,
+            Self::Private | Self::Shared | Self::UniqueLocal => {
+                AddressScope::Site
+            },
+            Self::Public
+            | Self::Documentation
+            | Self::Multicast
+            | Self::Reserved => AddressScope::Global,
+        }
+    }
+}
+
+/// Checks whether `ip_address` may be published to `zone_name`.
+pub fn check_address(
+    ip_address: IpAddr,
+    policy: PrivateAddressPolicy,
+    zone_name: &str,
+) -> Result<()> {
+    let class = AddressClass::of(ip_address);
+
+    if class == AddressClass::Public {
+        return Ok(());
+    }
+
+    if !class.is_private() {
+        return Err(eyre!(
+            "Refusing to publish {class} address '{ip_address}' to zone '{zone_name}'."
+        ));
+    }
+
+    match policy {
+        PrivateAddressPolicy::Allow => Ok(()),
+        PrivateAddressPolicy::Warn => {
+            warn!(
+                "Publishing {class} address '{ip_address}' to zone '{zone_name}'."
+            );
+            Ok(())
+        },
+        PrivateAddressPolicy::Refuse => {
+            Err(eyre!(
+                "Refusing to publish {class} address '{ip_address}' to zone '{zone_name}'. Set `private_addresses = \"allow\"` on the zone if this is intended."
+            ))
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_address_class() {
+        let cases = [
+            ("8.8.8.8", AddressClass::Public),
+            ("10.1.2.3", AddressClass::Private),
+            ("172.20.0.1", AddressClass::Private),
+            ("172.32.0.1", AddressClass::Public),
+            ("192.168.1.1", AddressClass::Private),
+            ("100.64.0.1", AddressClass::Shared),
+            ("100.128.0.1", AddressClass::Public),
+            ("169.254.1.1", AddressClass::LinkLocal),
+            ("203.0.113.1", AddressClass::Documentation),
+            ("198.19.0.1", AddressClass::Reserved),
+            ("255.255.255.255", AddressClass::Reserved),
+            ("2606:4700::1111", AddressClass::Public),
+            ("fd00::1", AddressClass::UniqueLocal),
+            ("fe80::1", AddressClass::LinkLocal),
+            ("2001:db8::1", AddressClass::Documentation),
+            ("3fff:fff::1", AddressClass::Documentation),
+            ("3fff:1000::1", AddressClass::Public),
+            ("::ffff:192.168.1.1", AddressClass::Private),
+            ("::1", AddressClass::Loopback),
+        ];
+
+        for (ip_address, class) in cases {
+            assert_eq!(
+                AddressClass::of(ip_address.parse().unwrap()),
+                class,
+                "{ip_address}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_scope() {
+        let cases = [
+            ("127.0.0.1", AddressScope::Host),
+            ("::", AddressScope::Host),
+            ("fe80::1", AddressScope::Link),
+            ("100.64.0.1", AddressScope::Site),
+            ("fd00::1", AddressScope::Site),
+            ("192.168.1.1", AddressScope::Site),
+            ("2606:4700::1111", AddressScope::Global),
+        ];
+
+        for (ip_address, scope) in cases {
+            assert_eq!(
+                AddressClass::of(ip_address.parse().unwrap()).scope(),
+                scope,
+                "{ip_address}"
+            );
+        }
+    }
+}
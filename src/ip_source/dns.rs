@@ -0,0 +1,285 @@
+//! A minimal DNS client, just enough to ask a resolver for our own address.
+//!
+//! Services such as OpenDNS answer an A or AAAA query for `myip.opendns.com`
+//! with the address the query came from. Others, like Google's
+//! `o-o.myaddr.l.google.com`, answer with a TXT record instead.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use color_eyre::Result;
+use color_eyre::eyre::{bail, eyre};
+
+use super::{IpLookupError, bind_udp, resolve_server};
+use crate::config::{DnsQueryType, PublicIpConfig};
+use crate::ip_family::IpFamily;
+
+const DNS_PORT: u16 = 53;
+const MAX_MESSAGE_SIZE: usize = 512;
+
+const TYPE_A: u16 = 1;
+const TYPE_TXT: u16 = 16;
+const TYPE_AAAA: u16 = 28;
+const CLASS_IN: u16 = 1;
+
+pub async fn query(
+    resolver: &str,
+    name: &str,
+    query_type: DnsQueryType,
+    family: IpFamily,
+    config: &PublicIpConfig,
+) -> Result<IpAddr> {
+    let resolver_address = resolve_server(resolver, DNS_PORT, family).await?;
+
+    let record_type = match (query_type, family) {
+        (DnsQueryType::Address, IpFamily::Ipv4) => TYPE_A,
+        (DnsQueryType::Address, IpFamily::Ipv6) => TYPE_AAAA,
+        (DnsQueryType::Txt, _) => TYPE_TXT,
+    };
+
+    let id = rand::random();
+    let request = build_query(id, name, record_type)?;
+
+    let socket = bind_udp(family, config).await?;
+    socket.connect(resolver_address).await.map_err(IpLookupError::from)?;
+    socket.send(&request).await.map_err(IpLookupError::from)?;
+
+    let mut buffer = [0; MAX_MESSAGE_SIZE];
+    // Waits for as long as the timeout of the source allows.
+    let length = socket.recv(&mut buffer).await.map_err(IpLookupError::from)?;
+
+    let answers = parse_response(&buffer[..length], id)
+        .map_err(|e| IpLookupError::Unparsable(e.to_string()))?;
+
+    let ip_address = answers
+        .into_iter()
+        .filter(|a| a.record_type == record_type)
+        .find_map(|a| parse_answer(&a, family))
+        .ok_or(IpLookupError::Unparsable(format!(
+            "No {family} address in the answer for '{name}'."
+        )))?;
+
+    Ok(ip_address)
+}
+
+fn build_query(id: u16, name: &str, record_type: u16) -> Result<Vec<u8>> {
+    let mut message = Vec::with_capacity(MAX_MESSAGE_SIZE);
+
+    message.extend(id.to_be_bytes());
+    // Flags: standard query, recursion desired.
+    message.extend(0x0100u16.to_be_bytes());
+    // One question, no answer, authority or additional records.
+    message.extend([0, 1, 0, 0, 0, 0, 0, 0]);
+
+    for label in name.trim_end_matches('.').split('.') {
+        let length = u8::try_from(label.len())
+            .ok()
+            .filter(|l| (1..64).contains(l))
+            .ok_or(eyre!("Invalid DNS name '{name}'"))?;
+
+        message.push(length);
+        message.extend(label.as_bytes());
+    }
+    message.push(0);
+
+    message.extend(record_type.to_be_bytes());
+    message.extend(CLASS_IN.to_be_bytes());
+
+    Ok(message)
+}
+
+#[derive(Debug)]
+struct Answer {
+    record_type: u16,
+    data: Vec<u8>,
+}
+
+fn parse_response(message: &[u8], id: u16) -> Result<Vec<Answer>> {
+    let mut reader = Reader { message, position: 0 };
+
+    if reader.u16()? != id {
+        bail!("DNS response has an unexpected ID.");
+    }
+
+    let flags = reader.u16()?;
+
+    if flags & 0x8000 == 0 {
+        bail!("DNS response is not a response.");
+    }
+
+    if flags & 0x0200 != 0 {
+        bail!("DNS response was truncated.");
+    }
+
+    match flags & 0x000f {
+        0 => (),
+        3 => bail!("DNS name does not exist."),
+        rcode => bail!("DNS query failed with RCODE {rcode}."),
+    }
+
+    let question_count = reader.u16()?;
+    let answer_count = reader.u16()?;
+    reader.skip(4)?;
+
+    for _ in 0..question_count {
+        reader.skip_name()?;
+        reader.skip(4)?;
+    }
+
+    let mut answers = Vec::with_capacity(answer_count.into());
+
+    for _ in 0..answer_count {
+        reader.skip_name()?;
+        let record_type = reader.u16()?;
+        reader.skip(6)?;
+        let length = reader.u16()?;
+        let data = reader.bytes(length.into())?.to_vec();
+
+        answers.push(Answer { record_type, data });
+    }
+
+    Ok(answers)
+}
+
+fn parse_answer(answer: &Answer, family: IpFamily) -> Option<IpAddr> {
+    let ip_address = match answer.record_type {
+        TYPE_A => {
+            IpAddr::V4(Ipv4Addr::from(
+                <[u8; 4]>::try_from(answer.data.as_slice()).ok()?,
+            ))
+        },
+        TYPE_AAAA => {
+            IpAddr::V6(Ipv6Addr::from(
+                <[u8; 16]>::try_from(answer.data.as_slice()).ok()?,
+            ))
+        },
+        TYPE_TXT => {
+            // TXT data is one or more length-prefixed strings.
+            let mut text = String::new();
+            let mut rest = answer.data.as_slice();
+
+            while let Some((length, tail)) = rest.split_first() {
+                let (chunk, tail) = tail.split_at_checked((*length).into())?;
+                text.push_str(std::str::from_utf8(chunk).ok()?);
+                rest = tail;
+            }
+
+            text.trim().parse().ok()?
+        },
+        _ => return None,
+    };
+
+    (IpFamily::of(ip_address) == family).then_some(ip_address)
+}
+
+struct Reader<'m> {
+    message: &'m [u8],
+    position: usize,
+}
+
+impl<'m> Reader<'m> {
+    fn bytes(&mut self, length: usize) -> Result<&'m [u8]> {
+        let bytes = self
+            .message
+            .get(self.position..self.position + length)
+            .ok_or(eyre!("DNS response is truncated."))?;
+
+        self.position += length;
+
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, length: usize) -> Result<()> {
+        self.bytes(length).map(|_| ())
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn skip_name(&mut self) -> Result<()> {
+        loop {
+            let length = self.bytes(1)?[0];
+
+            match length {
+                0 => return Ok(()),
+                // A compression pointer ends the name.
+                l if l & 0xc0 == 0xc0 => return self.skip(1),
+                l => self.skip(l.into())?,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::net::UdpSocket;
+
+    use super::*;
+
+    /// Answers every query with `answer` as its only answer record.
+    async fn stand_in_resolver(record_type: u16, answer: Vec<u8>) -> String {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let address = socket.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let mut buffer = [0; MAX_MESSAGE_SIZE];
+            let (length, peer) = socket.recv_from(&mut buffer).await.unwrap();
+
+            let mut response = buffer[..length].to_vec();
+            // Set QR and RA, and ANCOUNT to 1.
+            response[2] |= 0x80;
+            response[3] |= 0x80;
+            response[7] = 1;
+            // Name: pointer to the question.
+            response.extend([0xc0, 12]);
+            response.extend(record_type.to_be_bytes());
+            response.extend(CLASS_IN.to_be_bytes());
+            response.extend(0u32.to_be_bytes());
+            response.extend(u16::try_from(answer.len()).unwrap().to_be_bytes());
+            response.extend(answer);
+
+            socket.send_to(&response, peer).await.unwrap();
+        });
+
+        address.to_string()
+    }
+
+    #[tokio::test]
+    async fn test_query_address() -> Result<()> {
+        let resolver = stand_in_resolver(TYPE_A, vec![203, 0, 113, 7]).await;
+
+        let ip_address = query(
+            &resolver,
+            "myip.opendns.com",
+            DnsQueryType::Address,
+            IpFamily::Ipv4,
+            &PublicIpConfig::default(),
+        )
+        .await?;
+
+        assert_eq!(ip_address, IpAddr::from([203, 0, 113, 7]));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_query_txt() -> Result<()> {
+        let text = b"203.0.113.8";
+        let mut answer = vec![u8::try_from(text.len()).unwrap()];
+        answer.extend(text);
+
+        let resolver = stand_in_resolver(TYPE_TXT, answer).await;
+
+        let ip_address = query(
+            &resolver,
+            "o-o.myaddr.l.google.com",
+            DnsQueryType::Txt,
+            IpFamily::Ipv4,
+            &PublicIpConfig::default(),
+        )
+        .await?;
+
+        assert_eq!(ip_address, IpAddr::from([203, 0, 113, 8]));
+        Ok(())
+    }
+}
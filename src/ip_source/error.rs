@@ -0,0 +1,206 @@
+use std::time::Duration;
+
+use color_eyre::Report;
+use reqwest::StatusCode;
+
+/// Distinguishes the ways a lookup can fail, to decide whether to retry and
+/// which exit status to report.
+#[derive(Debug)]
+pub enum IpLookupError {
+    /// The source didn't answer in time, within the given duration if known.
+    Timeout(Option<Duration>),
+    /// The source couldn't be reached.
+    Connection(String),
+    /// The source has no address to report, like an interface that is down.
+    Unavailable(String),
+    /// The source answered with an HTTP error status.
+    HttpStatus(StatusCode),
+    /// The answer of the source doesn't contain an address.
+    Unparsable(String),
+}
+
+impl IpLookupError {
+    /// Exit status of a run that failed with this error, following the
+    /// conventions of `sysexits.h`.
+    #[must_use]
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            // EX_TEMPFAIL
+            IpLookupError::Timeout(_) => 75,
+            // EX_UNAVAILABLE
+            IpLookupError::Connection(_)
+            | IpLookupError::Unavailable(_)
+            | IpLookupError::HttpStatus(_) => 69,
+            // EX_DATAERR
+            IpLookupError::Unparsable(_) => 65,
+        }
+    }
+
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            IpLookupError::Timeout(_)
+            | IpLookupError::Connection(_)
+            | IpLookupError::Unavailable(_) => true,
+            IpLookupError::HttpStatus(status) => {
+                status.is_server_error()
+                    || *status == StatusCode::TOO_MANY_REQUESTS
+            },
+            IpLookupError::Unparsable(_) => false,
+        }
+    }
+
+    /// Finds the lookup error in `report`, if it was caused by one.
+    #[must_use]
+    pub fn find(report: &Report) -> Option<&Self> {
+        report.chain().find_map(|e| e.downcast_ref::<Self>())
+    }
+}
+
+impl From<reqwest::Error> for IpLookupError {
+    fn from(error: reqwest::Error) -> Self {
+        if error.is_timeout() {
+            IpLookupError::Timeout(None)
+        } else if let Some(status) = error.status() {
+            IpLookupError::HttpStatus(status)
+        } else if error.is_decode() || error.is_body() {
+            IpLookupError::Unparsable(error.to_string())
+        } else {
+            IpLookupError::Connection(error.to_string())
+        }
+    }
+}
+
+impl From<std::io::Error> for IpLookupError {
+    fn from(error: std::io::Error) -> Self {
+        if error.kind() == std::io::ErrorKind::TimedOut {
+            IpLookupError::Timeout(None)
+        } else {
+            IpLookupError::Connection(error.to_string())
+        }
+    }
+}
+
+impl std::fmt::Display for IpLookupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IpLookupError::Timeout(None) => write!(f, "Timed out"),
+            IpLookupError::Timeout(Some(duration)) => {
+                write!(f, "Timed out after {}s", duration.as_secs_f32())
+            },
+            IpLookupError::Connection(message) => {
+                write!(f, "Connection failed: {message}")
+            },
+            IpLookupError::Unavailable(message) => write!(f, "{message}"),
+            IpLookupError::HttpStatus(status) => {
+                write!(f, "HTTP status {status}")
+            },
+            IpLookupError::Unparsable(message) => {
+                write!(f, "Unparsable response: {message}")
+            },
+        }
+    }
+}
+
+impl std::error::Error for IpLookupError {}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use super::*;
+
+    /// Sends a request to a local socket that answers with `response`.
+    async fn lookup(response: &'static str) -> IpLookupError {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let length = stream.read(&mut [0; 1024]).unwrap();
+            assert!(length > 0);
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(200))
+            .build()
+            .unwrap();
+
+        let error = async { client.get(url).send().await?.error_for_status() }
+            .await
+            .unwrap_err();
+
+        IpLookupError::from(error)
+    }
+
+    #[test]
+    fn test_exit_code() {
+        assert_eq!(IpLookupError::Timeout(None).exit_code(), 75);
+        assert_eq!(IpLookupError::Connection(String::new()).exit_code(), 69);
+        assert_eq!(IpLookupError::Unavailable(String::new()).exit_code(), 69);
+        assert_eq!(
+            IpLookupError::HttpStatus(StatusCode::NOT_FOUND).exit_code(),
+            69
+        );
+        assert_eq!(IpLookupError::Unparsable(String::new()).exit_code(), 65);
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        let retryable = [
+            IpLookupError::Timeout(Some(Duration::from_secs(5))),
+            IpLookupError::Connection(String::new()),
+            IpLookupError::Unavailable(String::new()),
+            IpLookupError::HttpStatus(StatusCode::BAD_GATEWAY),
+            IpLookupError::HttpStatus(StatusCode::TOO_MANY_REQUESTS),
+        ];
+        assert!(retryable.iter().all(IpLookupError::is_retryable));
+
+        let permanent = [
+            IpLookupError::HttpStatus(StatusCode::NOT_FOUND),
+            IpLookupError::HttpStatus(StatusCode::UNAUTHORIZED),
+            IpLookupError::Unparsable(String::new()),
+        ];
+        assert!(!permanent.iter().any(IpLookupError::is_retryable));
+    }
+
+    #[tokio::test]
+    async fn test_from_reqwest_error() {
+        let error = lookup(
+            "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n",
+        )
+        .await;
+        assert!(matches!(
+            error,
+            IpLookupError::HttpStatus(StatusCode::SERVICE_UNAVAILABLE)
+        ));
+
+        // Connections are queued, but never answered.
+        let silent = TcpListener::bind("127.0.0.1:0").unwrap();
+        let error = reqwest::Client::builder()
+            .timeout(Duration::from_millis(200))
+            .build()
+            .unwrap()
+            .get(format!("http://{}", silent.local_addr().unwrap()))
+            .send()
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            IpLookupError::from(error),
+            IpLookupError::Timeout(None)
+        ));
+
+        let closed = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", closed.local_addr().unwrap());
+        drop(closed);
+
+        let error = reqwest::get(url).await.unwrap_err();
+        assert!(matches!(
+            IpLookupError::from(error),
+            IpLookupError::Connection(_)
+        ));
+    }
+}
@@ -0,0 +1,172 @@
+use std::net::IpAddr;
+
+use color_eyre::Result;
+use reqwest::Method;
+use serde_json::Value;
+
+use super::IpLookupError;
+use crate::config::{
+    HttpAuth, HttpMethod, HttpSource, PublicIpConfig, ResponseFormat,
+};
+use crate::ip_family::IpFamily;
+
+pub async fn query(
+    source: &HttpSource,
+    family: IpFamily,
+    config: &PublicIpConfig,
+) -> Result<IpAddr> {
+    // Binding to an address of a family forces the connection, and therefore
+    // the address echoed back, to be of that family.
+    let mut builder = reqwest::Client::builder()
+        .local_address(config.local_address(family))
+        .connect_timeout(config.connect_timeout())
+        .timeout(config.timeout());
+
+    if let Some(interface) = config.interface() {
+        builder = bind_interface(builder, interface)?;
+    }
+
+    let client = builder.build()?;
+
+    let method = match source.method {
+        HttpMethod::Get => Method::GET,
+        HttpMethod::Post => Method::POST,
+    };
+
+    let mut request = client.request(method, &source.url);
+
+    for (name, value) in &source.headers {
+        request = request.header(name, value);
+    }
+
+    request = match &source.auth {
+        Some(HttpAuth::Bearer(token)) => request.bearer_auth(token),
+        Some(HttpAuth::Basic { username, password }) => {
+            request.basic_auth(username, password.as_ref())
+        },
+        None => request,
+    };
+
+    let body = async { request.send().await?.error_for_status()?.text().await }
+        .await
+        .map_err(|e| {
+            match IpLookupError::from(e) {
+                IpLookupError::Timeout(_) => {
+                    IpLookupError::Timeout(Some(config.timeout()))
+                },
+                error => error,
+            }
+        })?;
+
+    Ok(parse_body(&body, &source.format)?)
+}
+
+#[cfg(any(target_os = "android", target_os = "linux", target_os = "macos"))]
+#[allow(clippy::unnecessary_wraps)]
+fn bind_interface(
+    builder: reqwest::ClientBuilder,
+    interface: &str,
+) -> Result<reqwest::ClientBuilder> {
+    Ok(builder.interface(interface))
+}
+
+#[cfg(not(any(
+    target_os = "android",
+    target_os = "linux",
+    target_os = "macos"
+)))]
+fn bind_interface(
+    _builder: reqwest::ClientBuilder,
+    _interface: &str,
+) -> Result<reqwest::ClientBuilder> {
+    Err(color_eyre::eyre::eyre!(
+        "Binding to an interface is not supported on this platform."
+    ))
+}
+
+fn parse_body(
+    body: &str,
+    format: &ResponseFormat,
+) -> Result<IpAddr, IpLookupError> {
+    let text = match format {
+        ResponseFormat::Text => body.trim().to_owned(),
+        ResponseFormat::Json(path) => {
+            let json: Value = serde_json::from_str(body)
+                .map_err(|e| IpLookupError::Unparsable(e.to_string()))?;
+
+            let value = path
+                .split('.')
+                .try_fold(&json, |value, field| {
+                    match field.parse::<usize>() {
+                        Ok(index) if value.is_array() => value.get(index),
+                        _ => value.get(field),
+                    }
+                })
+                .ok_or(IpLookupError::Unparsable(format!(
+                    "Response has no field '{path}'."
+                )))?;
+
+            value
+                .as_str()
+                .ok_or(IpLookupError::Unparsable(format!(
+                    "Field '{path}' in response is not a string."
+                )))?
+                .to_owned()
+        },
+        ResponseFormat::KeyValue(key) => {
+            body.lines()
+                .find_map(|l| {
+                    let (k, v) = l.split_once('=')?;
+                    (k.trim() == key).then(|| v.trim().to_owned())
+                })
+                .ok_or(IpLookupError::Unparsable(format!(
+                    "Response has no key '{key}'."
+                )))?
+        },
+    };
+
+    text.parse().map_err(|_| {
+        IpLookupError::Unparsable(format!(
+            "Response '{text}' is not a valid IP address."
+        ))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_body() -> Result<()> {
+        let expected = IpAddr::from([203, 0, 113, 1]);
+
+        assert_eq!(
+            parse_body("203.0.113.1\n", &ResponseFormat::Text)?,
+            expected
+        );
+
+        assert_eq!(
+            parse_body(
+                r#"{"ip":"203.0.113.1"}"#,
+                &ResponseFormat::Json("ip".to_owned())
+            )?,
+            expected
+        );
+
+        assert_eq!(
+            parse_body(
+                r#"{"data":{"addresses":["203.0.113.1"]}}"#,
+                &ResponseFormat::Json("data.addresses.0".to_owned())
+            )?,
+            expected
+        );
+
+        let trace = "fl=123\nh=example.com\nip=203.0.113.1\nts=1.0\n";
+        assert_eq!(
+            parse_body(trace, &ResponseFormat::KeyValue("ip".to_owned()))?,
+            expected
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv6Addr};
+
+use color_eyre::Result;
+use tracing::trace;
+
+use super::IpLookupError;
+use crate::config::AddressScope;
+use crate::ip_family::IpFamily;
+use crate::ip_policy::AddressClass;
+
+// Address flags as listed in /proc/net/if_inet6, see `linux/if_addr.h`.
+const IFA_F_TEMPORARY: u32 = 0x01;
+const IFA_F_DADFAILED: u32 = 0x08;
+const IFA_F_DEPRECATED: u32 = 0x20;
+const IFA_F_TENTATIVE: u32 = 0x40;
+
+pub fn query(
+    interface: Option<&str>,
+    scope: AddressScope,
+    family: IpFamily,
+) -> Result<IpAddr> {
+    let ipv6_flags = read_ipv6_flags();
+
+    let mut candidates = Vec::new();
+
+    for (name, ip_address) in interface_addresses()? {
+        if interface.is_some_and(|i| i != name)
+            || IpFamily::of(ip_address) != family
+            || AddressClass::of(ip_address).scope() != scope
+        {
+            continue;
+        }
+
+        let flags = match ip_address {
+            IpAddr::V4(_) => 0,
+            IpAddr::V6(ip) => ipv6_flags.get(&ip).copied().unwrap_or_default(),
+        };
+
+        if flags & (IFA_F_TENTATIVE | IFA_F_DADFAILED) != 0 {
+            trace!("Skipping unusable address '{ip_address}' on {name}");
+            continue;
+        }
+
+        candidates.push((preference(flags), ip_address));
+    }
+
+    // `min_by_key` returns the first of equally preferred addresses.
+    let ip_address = candidates
+        .into_iter()
+        .min_by_key(|(p, _)| *p)
+        .map(|(_, ip)| ip)
+        .ok_or(IpLookupError::Unavailable(format!(
+            "No {scope} {family} address found on {}.",
+            interface.unwrap_or("any interface")
+        )))?;
+
+    Ok(ip_address)
+}
+
+/// Stable addresses are preferred over temporary (privacy) addresses, which
+/// are preferred over deprecated addresses.
+fn preference(flags: u32) -> u8 {
+    match (flags & IFA_F_DEPRECATED != 0, flags & IFA_F_TEMPORARY != 0) {
+        (true, _) => 2,
+        (false, true) => 1,
+        (false, false) => 0,
+    }
+}
+
+#[cfg(unix)]
+fn interface_addresses() -> Result<Vec<(String, IpAddr)>> {
+    let addresses = nix::ifaddrs::getifaddrs()
+        .map_err(|e| IpLookupError::from(std::io::Error::from(e)))?
+        .filter_map(|a| {
+            let address = a.address?;
+
+            let ip_address = if let Some(sin) = address.as_sockaddr_in() {
+                IpAddr::V4(sin.ip())
+            } else {
+                IpAddr::V6(address.as_sockaddr_in6()?.ip())
+            };
+
+            Some((a.interface_name, ip_address))
+        })
+        .collect();
+
+    Ok(addresses)
+}
+
+#[cfg(not(unix))]
+fn interface_addresses() -> Result<Vec<(String, IpAddr)>> {
+    Err(color_eyre::eyre::eyre!(
+        "Reading interface addresses is only supported on unix."
+    ))
+}
+
+/// `getifaddrs` doesn't expose IPv6 address flags, Linux lists them in
+/// `/proc/net/if_inet6`. On other systems every address is treated as stable.
+fn read_ipv6_flags() -> HashMap<Ipv6Addr, u32> {
+    let Ok(body) = fs_err::read_to_string("/proc/net/if_inet6") else {
+        return HashMap::new();
+    };
+
+    body.lines().filter_map(parse_if_inet6_line).collect()
+}
+
+fn parse_if_inet6_line(line: &str) -> Option<(Ipv6Addr, u32)> {
+    // address ifindex prefix_length scope flags name
+    let mut fields = line.split_whitespace();
+
+    let address = u128::from_str_radix(fields.next()?, 16).ok()?;
+    let flags = u32::from_str_radix(fields.nth(3)?, 16).ok()?;
+
+    Some((Ipv6Addr::from(address), flags))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_if_inet6_line() {
+        let line = "20010db8000000000000000000000001 02 40 00 01     eth0";
+
+        assert_eq!(
+            parse_if_inet6_line(line),
+            Some(("2001:db8::1".parse().unwrap(), IFA_F_TEMPORARY))
+        );
+    }
+
+    #[test]
+    fn test_preference() {
+        assert!(preference(0) < preference(IFA_F_TEMPORARY));
+        assert!(preference(IFA_F_TEMPORARY) < preference(IFA_F_DEPRECATED));
+        assert_eq!(
+            preference(IFA_F_TEMPORARY | IFA_F_DEPRECATED),
+            preference(IFA_F_DEPRECATED)
+        );
+    }
+}
@@ -0,0 +1,326 @@
+//! Public IP address detection.
+//!
+//! Every configured [`IpSource`] is queried for a single address family. How
+//! the answers of multiple sources are combined is determined by the
+//! [`IpStrategy`]:
+//!
+//! - [`IpStrategy::Fallback`] queries the sources in order and uses the first
+//!   address returned.
+//! - [`IpStrategy::Consensus`] queries every source and only accepts an
+//!   address if enough sources agree on it.
+
+mod dns;
+mod error;
+mod http;
+pub mod interface;
+mod router;
+mod stun;
+
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+pub use error::IpLookupError;
+use futures_util::future::join_all;
+use indexmap::IndexMap;
+use tokio::net::{UdpSocket, lookup_host};
+use tokio::time::{sleep, timeout};
+use tracing::{debug, warn};
+
+use crate::config::{IpSource, IpSourceConfig, IpStrategy, PublicIpConfig};
+use crate::ip_family::IpFamily;
+
+impl IpSource {
+    /// Query this source for the public address of `family`, retrying on
+    /// transient errors.
+    pub async fn query(
+        &self,
+        family: IpFamily,
+        config: &PublicIpConfig,
+    ) -> Result<IpAddr> {
+        let mut attempt = 0;
+
+        loop {
+            match self.query_attempt(family, config).await {
+                Err(error)
+                    if attempt < config.retries()
+                        && IpLookupError::find(&error)
+                            .is_some_and(IpLookupError::is_retryable) =>
+                {
+                    let delay = backoff(config.retry_delay(), attempt);
+                    debug!("{self}: {error}, retrying in {delay:?}");
+                    sleep(delay).await;
+                    attempt += 1;
+                },
+                result => return result,
+            }
+        }
+    }
+
+    /// Query this source once, within the timeout of `config`.
+    pub async fn query_attempt(
+        &self,
+        family: IpFamily,
+        config: &PublicIpConfig,
+    ) -> Result<IpAddr> {
+        timeout(config.timeout(), self.query_once(family, config))
+            .await
+            .unwrap_or(Err(
+                IpLookupError::Timeout(Some(config.timeout())).into()
+            ))
+    }
+
+    async fn query_once(
+        &self,
+        family: IpFamily,
+        config: &PublicIpConfig,
+    ) -> Result<IpAddr> {
+        let ip_address = match self {
+            IpSource::Http(http) => http::query(http, family, config).await?,
+            IpSource::Interface { interface, scope } => {
+                interface::query(interface.as_deref(), *scope, family)?
+            },
+            IpSource::Dns { resolver, name, query } => {
+                dns::query(resolver, name, *query, family, config).await?
+            },
+            IpSource::Stun { server } => {
+                stun::query(server, family, config).await?
+            },
+            IpSource::Upnp { location } => {
+                router::query_upnp(location.as_deref(), family).await?
+            },
+            IpSource::NatPmp { gateway } => {
+                router::query_nat_pmp(gateway.as_deref(), family).await?
+            },
+            IpSource::Pcp { gateway } => {
+                router::query_pcp(gateway.as_deref(), family).await?
+            },
+        };
+
+        if IpFamily::of(ip_address) == family {
+            Ok(ip_address)
+        } else {
+            Err(eyre!("Returned '{ip_address}' for an {family} lookup."))
+        }
+    }
+}
+
+/// Doubles `delay` with every attempt, with up to 50% jitter either way so
+/// retries from many hosts don't align.
+fn backoff(delay: Duration, attempt: u32) -> Duration {
+    let jitter = rand::random_range(0.5..1.5);
+    delay.saturating_mul(2u32.saturating_pow(attempt)).mul_f64(jitter)
+}
+
+/// Binds a UDP socket to the local address and interface configured for
+/// `family`.
+async fn bind_udp(
+    family: IpFamily,
+    config: &PublicIpConfig,
+) -> Result<UdpSocket> {
+    let socket =
+        UdpSocket::bind(SocketAddr::new(config.local_address(family), 0))
+            .await?;
+
+    if let Some(interface) = config.interface() {
+        #[cfg(any(target_os = "android", target_os = "linux"))]
+        socket.bind_device(Some(interface.as_bytes()))?;
+
+        #[cfg(not(any(target_os = "android", target_os = "linux")))]
+        return Err(eyre!(
+            "Binding to interface '{interface}' is not supported on this platform."
+        ));
+    }
+
+    Ok(socket)
+}
+
+/// Looks up `server`, which can be a host name or address with an optional
+/// port, and picks an address of `family`.
+async fn resolve_server(
+    server: &str,
+    default_port: u16,
+    family: IpFamily,
+) -> Result<SocketAddr> {
+    let addresses: Vec<SocketAddr> = if let Ok(address) = server.parse() {
+        vec![address]
+    } else if let Ok(ip_address) = server.parse::<IpAddr>() {
+        vec![SocketAddr::new(ip_address, default_port)]
+    } else if server.contains(':') {
+        lookup_host(server).await.map_err(IpLookupError::from)?.collect()
+    } else {
+        lookup_host((server, default_port))
+            .await
+            .map_err(IpLookupError::from)?
+            .collect()
+    };
+
+    addresses
+        .into_iter()
+        .find(|a| IpFamily::of(a.ip()) == family)
+        .ok_or(eyre!("'{server}' has no {family} address."))
+}
+
+/// Determine the public address of `family` using the configured sources and
+/// strategy. Returns `None` if no sources are configured for `family`.
+pub async fn detect_public_ip_address(
+    config: &PublicIpConfig,
+    family: IpFamily,
+) -> Result<Option<IpAddr>> {
+    let sources: Vec<_> =
+        config.sources(family).iter().map(IpSourceConfig::source).collect();
+
+    if sources.is_empty() {
+        return Ok(None);
+    }
+
+    let ip_address = match config.strategy(family) {
+        IpStrategy::Fallback => fallback(&sources, family, config).await?,
+        IpStrategy::Consensus(quorum) => {
+            consensus(&sources, family, quorum, config).await?
+        },
+    };
+
+    Ok(Some(ip_address))
+}
+
+async fn fallback(
+    sources: &[IpSource],
+    family: IpFamily,
+    config: &PublicIpConfig,
+) -> Result<IpAddr> {
+    let mut last_error = None;
+
+    for source in sources {
+        match source.query(family, config).await {
+            Ok(ip_address) => {
+                debug!("{source} returned '{ip_address}'");
+                return Ok(ip_address);
+            },
+            Err(error) => {
+                warn!("Unable to query {source}: {error}");
+                last_error = Some(error);
+            },
+        }
+    }
+
+    // Keep the last error, so its kind determines the exit status.
+    let message = format!("None of the {family} sources returned an address.");
+    Err(last_error.map_or(eyre!(message.clone()), |e| e.wrap_err(message)))
+}
+
+async fn consensus(
+    sources: &[IpSource],
+    family: IpFamily,
+    quorum: usize,
+    config: &PublicIpConfig,
+) -> Result<IpAddr> {
+    if quorum == 0 || quorum > sources.len() {
+        return Err(eyre!(
+            "Consensus of {quorum} is impossible with {} {family} sources.",
+            sources.len()
+        ));
+    }
+
+    let results =
+        join_all(sources.iter().map(|s| s.query(family, config))).await;
+
+    let mut votes: IndexMap<IpAddr, usize> = IndexMap::new();
+    let mut last_error = None;
+
+    for (source, result) in sources.iter().zip(results) {
+        match result {
+            Ok(ip_address) => {
+                debug!("{source} returned '{ip_address}'");
+                *votes.entry(ip_address).or_default() += 1;
+            },
+            Err(error) => {
+                warn!("Unable to query {source}: {error}");
+                last_error = Some(error);
+            },
+        }
+    }
+
+    agreed_address(&votes, quorum, family).map_err(|message| {
+        // Without any answers, keep the last error so its kind determines
+        // the exit status.
+        match last_error {
+            Some(error) if votes.is_empty() => error.wrap_err(message),
+            _ => eyre!(message),
+        }
+    })
+}
+
+/// The address at least `quorum` sources agree on. When no address, or more
+/// than one, reaches the quorum, no address is agreed on.
+fn agreed_address(
+    votes: &IndexMap<IpAddr, usize>,
+    quorum: usize,
+    family: IpFamily,
+) -> Result<IpAddr, String> {
+    let agreed: Vec<_> = votes
+        .iter()
+        .filter(|(_, n)| **n >= quorum)
+        .map(|(ip_address, _)| *ip_address)
+        .collect();
+
+    if let [ip_address] = agreed[..] {
+        return Ok(ip_address);
+    }
+
+    let tally = votes
+        .iter()
+        .map(|(ip_address, n)| format!("'{ip_address}' ({n})"))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    if agreed.is_empty() {
+        Err(format!(
+            "Fewer than {quorum} {family} sources agree on an address: \
+             [{tally}]"
+        ))
+    } else {
+        Err(format!(
+            "{} {family} addresses each have {quorum} sources agreeing: \
+             [{tally}]",
+            agreed.len()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let delay = Duration::from_secs(1);
+
+        for attempt in 0..4 {
+            let base = delay * 2u32.pow(attempt);
+            let backoff = backoff(delay, attempt);
+            assert!(
+                backoff >= base / 2 && backoff < base * 3 / 2,
+                "{backoff:?}"
+            );
+        }
+
+        // Saturates instead of overflowing.
+        assert!(backoff(delay, 64) >= delay * u32::MAX / 2);
+    }
+
+    #[test]
+    fn test_agreed_address() {
+        let a: IpAddr = "198.51.100.1".parse().unwrap();
+        let b: IpAddr = "198.51.100.2".parse().unwrap();
+
+        let votes = IndexMap::from([(a, 1), (b, 2)]);
+        assert_eq!(agreed_address(&votes, 2, IpFamily::Ipv4), Ok(b));
+        assert!(agreed_address(&votes, 3, IpFamily::Ipv4).is_err());
+
+        // A tie is not an agreement, whichever source answered first.
+        let votes = IndexMap::from([(a, 2), (b, 2)]);
+        assert!(agreed_address(&votes, 2, IpFamily::Ipv4).is_err());
+    }
+}
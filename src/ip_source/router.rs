@@ -0,0 +1,377 @@
+//! Ask the local router for its WAN address.
+//!
+//! - `UPnP` IGD: discover the gateway with SSDP, then call
+//!   `GetExternalIPAddress` on its WAN connection service.
+//! - NAT-PMP (RFC 6886): request the external address from the gateway.
+//! - PCP (RFC 6887): request a short-lived mapping and read the assigned
+//!   external address from the response.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+
+use color_eyre::Result;
+use color_eyre::eyre::{bail, eyre};
+use reqwest::Url;
+use tokio::net::UdpSocket;
+use tokio::time::{Instant, timeout_at};
+use tracing::debug;
+
+use super::resolve_server;
+use crate::config::AddressScope;
+use crate::ip_family::IpFamily;
+use crate::ip_policy::AddressClass;
+use crate::xml;
+
+const SSDP_ADDRESS: &str = "239.255.255.250:1900";
+const IGD_SEARCH_TARGET: &str =
+    "urn:schemas-upnp-org:device:InternetGatewayDevice:1";
+const WAN_SERVICE_TYPES: [&str; 2] = ["WANIPConnection", "WANPPPConnection"];
+
+const NAT_PMP_PORT: u16 = 5351;
+const PCP_PORT: u16 = 5351;
+const PCP_VERSION: u8 = 2;
+const PCP_OPCODE_MAP: u8 = 1;
+const PCP_LIFETIME: u32 = 30;
+const PROTOCOL_UDP: u8 = 17;
+
+/// NAT-PMP and PCP retransmit with a doubling interval, starting at 250 ms
+/// and capped at 64 seconds (RFC 6886).
+const INITIAL_RETRANSMIT: Duration = Duration::from_millis(250);
+const MAX_RETRANSMIT: Duration = Duration::from_secs(64);
+
+pub async fn query_upnp(
+    location: Option<&str>,
+    family: IpFamily,
+) -> Result<IpAddr> {
+    require_ipv4("UPnP", family)?;
+
+    let location = match location {
+        Some(location) => location.to_owned(),
+        None => discover_igd().await?,
+    };
+
+    debug!("Using UPnP device description at {location}");
+
+    let location = Url::parse(&location)?;
+    let description = reqwest::get(location.clone())
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+
+    let (service_type, control_url) = find_wan_service(&description)
+        .ok_or(eyre!("{location} has no WAN connection service."))?;
+
+    let base = xml::element_text(&description, "URLBase")
+        .and_then(|b| Url::parse(&b).ok())
+        .unwrap_or(location);
+    let control_url = base.join(&control_url)?;
+
+    let body = format!(
+        r#"<?xml version="1.0"?>
+<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/">
+<s:Body><u:GetExternalIPAddress xmlns:u="{service_type}"/></s:Body>
+</s:Envelope>"#
+    );
+
+    let response = reqwest::Client::new()
+        .post(control_url)
+        .header("Content-Type", r#"text/xml; charset="utf-8""#)
+        .header(
+            "SOAPAction",
+            format!(r#""{service_type}#GetExternalIPAddress""#),
+        )
+        .body(body)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+
+    let ip_address: IpAddr =
+        xml::element_text(&response, "NewExternalIPAddress")
+            .ok_or(eyre!("Router returned no external address."))?
+            .parse()?;
+
+    check_wan_address(ip_address)
+}
+
+pub async fn query_nat_pmp(
+    gateway: Option<&str>,
+    family: IpFamily,
+) -> Result<IpAddr> {
+    require_ipv4("NAT-PMP", family)?;
+
+    let gateway = gateway_address(gateway, NAT_PMP_PORT, family).await?;
+
+    // Version 0, opcode 0: external address request.
+    let response =
+        request(gateway, &[0, 0], |r| r.get(..2) == Some(&[0, 128])).await?;
+
+    let result_code = u16::from_be_bytes(
+        response
+            .get(2..4)
+            .ok_or(eyre!("NAT-PMP response is truncated."))?
+            .try_into()?,
+    );
+
+    if result_code != 0 {
+        bail!("NAT-PMP request failed with result code {result_code}.");
+    }
+
+    let octets: [u8; 4] = response
+        .get(8..12)
+        .ok_or(eyre!("NAT-PMP response is truncated."))?
+        .try_into()?;
+
+    check_wan_address(IpAddr::V4(Ipv4Addr::from(octets)))
+}
+
+pub async fn query_pcp(
+    gateway: Option<&str>,
+    family: IpFamily,
+) -> Result<IpAddr> {
+    let gateway = gateway_address(gateway, PCP_PORT, family).await?;
+
+    // The client address in the request must be the one the gateway sees.
+    let socket =
+        UdpSocket::bind(SocketAddr::new(family.unspecified_address(), 0))
+            .await?;
+    socket.connect(gateway).await?;
+    let local_address = socket.local_addr()?;
+    drop(socket);
+
+    let nonce: [u8; 12] = rand::random();
+
+    let mut message = Vec::with_capacity(60);
+    message.extend([PCP_VERSION, PCP_OPCODE_MAP, 0, 0]);
+    message.extend(PCP_LIFETIME.to_be_bytes());
+    message.extend(to_ipv6(local_address.ip()).octets());
+    message.extend(nonce);
+    message.extend([PROTOCOL_UDP, 0, 0, 0]);
+    message.extend(local_address.port().to_be_bytes());
+    // No suggested external port or address.
+    message.extend([0, 0]);
+    message.extend(to_ipv6(family.unspecified_address()).octets());
+
+    let response = request(gateway, &message, |r| {
+        r.get(..2) == Some(&[PCP_VERSION, 0x80 | PCP_OPCODE_MAP])
+            && r.get(24..36) == Some(&nonce)
+    })
+    .await?;
+
+    let result_code =
+        *response.get(3).ok_or(eyre!("PCP response is truncated."))?;
+
+    if result_code != 0 {
+        bail!("PCP request failed with result code {result_code}.");
+    }
+
+    let octets: [u8; 16] = response
+        .get(44..60)
+        .ok_or(eyre!("PCP response is truncated."))?
+        .try_into()?;
+    let ip_address = Ipv6Addr::from(octets);
+
+    let ip_address = match ip_address.to_ipv4_mapped() {
+        Some(ip) => IpAddr::V4(ip),
+        None => IpAddr::V6(ip_address),
+    };
+
+    check_wan_address(ip_address)
+}
+
+fn require_ipv4(protocol: &str, family: IpFamily) -> Result<()> {
+    if family == IpFamily::Ipv4 {
+        Ok(())
+    } else {
+        Err(eyre!("{protocol} can only report an IPv4 address."))
+    }
+}
+
+/// A router that reports a private WAN address is itself behind NAT, so its
+/// WAN address isn't our public address.
+fn check_wan_address(ip_address: IpAddr) -> Result<IpAddr> {
+    let scope = AddressClass::of(ip_address).scope();
+
+    if scope == AddressScope::Global {
+        Ok(ip_address)
+    } else {
+        Err(eyre!(
+            "Router reports a {scope} WAN address '{ip_address}', it is behind another NAT (double NAT)."
+        ))
+    }
+}
+
+fn to_ipv6(ip_address: IpAddr) -> Ipv6Addr {
+    match ip_address {
+        IpAddr::V4(ip) => ip.to_ipv6_mapped(),
+        IpAddr::V6(ip) => ip,
+    }
+}
+
+async fn gateway_address(
+    gateway: Option<&str>,
+    port: u16,
+    family: IpFamily,
+) -> Result<SocketAddr> {
+    match gateway {
+        Some(gateway) => resolve_server(gateway, port, family).await,
+        None if family == IpFamily::Ipv4 => {
+            Ok(SocketAddr::new(default_gateway()?.into(), port))
+        },
+        None => bail!("The gateway must be configured for IPv6."),
+    }
+}
+
+/// Reads the IPv4 default gateway from the Linux routing table.
+fn default_gateway() -> Result<Ipv4Addr> {
+    let routes = fs_err::read_to_string("/proc/net/route").map_err(|_| {
+        eyre!("Unable to determine the default gateway, configure `gateway`.")
+    })?;
+
+    // Iface Destination Gateway ..., addresses are little-endian hex.
+    routes
+        .lines()
+        .skip(1)
+        .find_map(|l| {
+            let mut fields = l.split_whitespace().skip(1);
+            let destination = fields.next()?;
+            let gateway = u32::from_str_radix(fields.next()?, 16).ok()?;
+            (destination == "00000000")
+                .then(|| Ipv4Addr::from(gateway.to_le_bytes()))
+        })
+        .ok_or(eyre!("No default gateway found, configure `gateway`."))
+}
+
+/// Sends `message` to `gateway`, retransmitting until a response satisfying
+/// `is_response` arrives or the timeout of the source ends the request.
+async fn request(
+    gateway: SocketAddr,
+    message: &[u8],
+    is_response: impl Fn(&[u8]) -> bool,
+) -> Result<Vec<u8>> {
+    let local_address = match gateway {
+        SocketAddr::V4(_) => IpFamily::Ipv4,
+        SocketAddr::V6(_) => IpFamily::Ipv6,
+    }
+    .unspecified_address();
+
+    let socket = UdpSocket::bind(SocketAddr::new(local_address, 0)).await?;
+    socket.connect(gateway).await?;
+
+    let mut buffer = [0; 1100];
+    let mut interval = INITIAL_RETRANSMIT;
+
+    loop {
+        socket.send(message).await?;
+
+        let deadline = Instant::now() + interval;
+
+        while let Ok(received) =
+            timeout_at(deadline, socket.recv(&mut buffer)).await
+        {
+            let response = &buffer[..received?];
+
+            if is_response(response) {
+                return Ok(response.to_vec());
+            }
+        }
+
+        interval = (interval * 2).min(MAX_RETRANSMIT);
+    }
+}
+
+async fn discover_igd() -> Result<String> {
+    let socket = UdpSocket::bind("0.0.0.0:0").await?;
+
+    let request = format!(
+        "M-SEARCH * HTTP/1.1\r\nHOST: {SSDP_ADDRESS}\r\nMAN: \"ssdp:discover\"\r\nMX: 2\r\nST: {IGD_SEARCH_TARGET}\r\n\r\n"
+    );
+    socket.send_to(request.as_bytes(), SSDP_ADDRESS).await?;
+
+    let mut buffer = [0; 2048];
+
+    // Waits for a device for as long as the timeout of the source allows.
+    let (length, peer) = socket.recv_from(&mut buffer).await?;
+
+    let response = String::from_utf8_lossy(&buffer[..length]);
+
+    response
+        .lines()
+        .find_map(|l| {
+            let (name, value) = l.split_once(':')?;
+            name.eq_ignore_ascii_case("location")
+                .then(|| value.trim().to_owned())
+        })
+        .ok_or(eyre!("SSDP response from {peer} has no location."))
+}
+
+/// Finds the service type and control URL of the first WAN connection
+/// service in a device description.
+fn find_wan_service(description: &str) -> Option<(String, String)> {
+    xml::elements(description, "service").find_map(|service| {
+        let service_type = xml::element_text(service, "serviceType")?;
+
+        if WAN_SERVICE_TYPES.iter().any(|t| service_type.contains(t)) {
+            Some((service_type, xml::element_text(service, "controlURL")?))
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_query_nat_pmp() -> Result<()> {
+        let socket = UdpSocket::bind("127.0.0.1:0").await?;
+        let gateway = socket.local_addr()?.to_string();
+
+        tokio::spawn(async move {
+            let mut buffer = [0; 2];
+            let (_, peer) = socket.recv_from(&mut buffer).await.unwrap();
+
+            let mut response = vec![0, 128, 0, 0, 0, 0, 0, 1];
+            response.extend([203, 0, 113, 9]);
+
+            socket.send_to(&response, peer).await.unwrap();
+        });
+
+        let ip_address = query_nat_pmp(Some(&gateway), IpFamily::Ipv4).await?;
+
+        assert_eq!(ip_address, IpAddr::from([203, 0, 113, 9]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_double_nat() {
+        assert!(check_wan_address(IpAddr::from([100, 64, 1, 2])).is_err());
+        assert!(check_wan_address(IpAddr::from([192, 168, 1, 2])).is_err());
+        assert!(check_wan_address(IpAddr::from([203, 0, 113, 9])).is_ok());
+    }
+
+    #[test]
+    fn test_find_wan_service() {
+        let description = r"<root><device><serviceList>
+            <service>
+                <serviceType>urn:schemas-upnp-org:service:Layer3Forwarding:1</serviceType>
+                <controlURL>/l3f</controlURL>
+            </service>
+            <service>
+                <serviceType>urn:schemas-upnp-org:service:WANIPConnection:1</serviceType>
+                <controlURL>/ctl/IPConn</controlURL>
+            </service>
+        </serviceList></device></root>";
+
+        assert_eq!(
+            find_wan_service(description),
+            Some((
+                "urn:schemas-upnp-org:service:WANIPConnection:1".to_owned(),
+                "/ctl/IPConn".to_owned()
+            ))
+        );
+    }
+}
@@ -0,0 +1,230 @@
+//! A minimal STUN client (RFC 5389), which only sends Binding Requests.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use color_eyre::Result;
+use color_eyre::eyre::{bail, eyre};
+
+use super::{IpLookupError, bind_udp, resolve_server};
+use crate::config::PublicIpConfig;
+use crate::ip_family::IpFamily;
+
+const STUN_PORT: u16 = 3478;
+const MAX_MESSAGE_SIZE: usize = 576;
+
+const MAGIC_COOKIE: u32 = 0x2112_a442;
+const HEADER_SIZE: usize = 20;
+
+const BINDING_REQUEST: u16 = 0x0001;
+const BINDING_SUCCESS: u16 = 0x0101;
+const BINDING_ERROR: u16 = 0x0111;
+
+const ATTR_MAPPED_ADDRESS: u16 = 0x0001;
+const ATTR_XOR_MAPPED_ADDRESS: u16 = 0x0020;
+
+const FAMILY_IPV4: u8 = 0x01;
+const FAMILY_IPV6: u8 = 0x02;
+
+pub async fn query(
+    server: &str,
+    family: IpFamily,
+    config: &PublicIpConfig,
+) -> Result<IpAddr> {
+    let server_address = resolve_server(server, STUN_PORT, family).await?;
+
+    let transaction_id: [u8; 12] = rand::random();
+
+    let socket = bind_udp(family, config).await?;
+    socket.connect(server_address).await.map_err(IpLookupError::from)?;
+    socket
+        .send(&build_binding_request(transaction_id))
+        .await
+        .map_err(IpLookupError::from)?;
+
+    let mut buffer = [0; MAX_MESSAGE_SIZE];
+    // Waits for as long as the timeout of the source allows.
+    let length = socket.recv(&mut buffer).await.map_err(IpLookupError::from)?;
+
+    let ip_address = parse_binding_response(&buffer[..length], transaction_id)
+        .map_err(|e| IpLookupError::Unparsable(e.to_string()))?;
+
+    Ok(ip_address)
+}
+
+fn build_binding_request(transaction_id: [u8; 12]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(HEADER_SIZE);
+
+    message.extend(BINDING_REQUEST.to_be_bytes());
+    // No attributes.
+    message.extend(0u16.to_be_bytes());
+    message.extend(MAGIC_COOKIE.to_be_bytes());
+    message.extend(transaction_id);
+
+    message
+}
+
+fn parse_binding_response(
+    message: &[u8],
+    transaction_id: [u8; 12],
+) -> Result<IpAddr> {
+    if message.len() < HEADER_SIZE {
+        bail!("STUN response is truncated.");
+    }
+
+    let message_type = u16::from_be_bytes([message[0], message[1]]);
+    let length = usize::from(u16::from_be_bytes([message[2], message[3]]));
+
+    if message[4..8] != MAGIC_COOKIE.to_be_bytes()
+        || message[8..HEADER_SIZE] != transaction_id
+    {
+        bail!("STUN response doesn't match the request.");
+    }
+
+    match message_type {
+        BINDING_SUCCESS => (),
+        BINDING_ERROR => bail!("STUN server returned an error response."),
+        other => bail!("Unexpected STUN message type {other:#06x}."),
+    }
+
+    let mut attributes = message
+        .get(HEADER_SIZE..HEADER_SIZE + length)
+        .ok_or(eyre!("STUN response is truncated."))?;
+
+    let mut mapped_address = None;
+
+    while attributes.len() >= 4 {
+        let attribute_type = u16::from_be_bytes([attributes[0], attributes[1]]);
+        let attribute_length =
+            usize::from(u16::from_be_bytes([attributes[2], attributes[3]]));
+        let value = attributes
+            .get(4..4 + attribute_length)
+            .ok_or(eyre!("STUN attribute is truncated."))?;
+
+        match attribute_type {
+            ATTR_XOR_MAPPED_ADDRESS => {
+                return parse_address(value, Some(transaction_id));
+            },
+            // Servers implementing only RFC 3489 don't send the XOR variant.
+            ATTR_MAPPED_ADDRESS => {
+                mapped_address = Some(parse_address(value, None)?);
+            },
+            _ => (),
+        }
+
+        // Attributes are padded to a multiple of 4 bytes.
+        let padded_length = attribute_length.next_multiple_of(4);
+        attributes = attributes.get(4 + padded_length..).unwrap_or_default();
+    }
+
+    mapped_address.ok_or(eyre!("STUN response contains no mapped address."))
+}
+
+/// Parses a (XOR-)MAPPED-ADDRESS value. The address is XOR-ed with the magic
+/// cookie and transaction ID if `transaction_id` is given.
+fn parse_address(
+    value: &[u8],
+    transaction_id: Option<[u8; 12]>,
+) -> Result<IpAddr> {
+    let mut key = [0; 16];
+
+    if let Some(transaction_id) = transaction_id {
+        key[..4].copy_from_slice(&MAGIC_COOKIE.to_be_bytes());
+        key[4..].copy_from_slice(&transaction_id);
+    }
+
+    let family = *value.get(1).ok_or(eyre!("STUN address is truncated."))?;
+    let address = value.get(4..).unwrap_or_default();
+
+    let xor = |bytes: &[u8]| -> Vec<u8> {
+        bytes.iter().zip(key).map(|(b, k)| b ^ k).collect()
+    };
+
+    match family {
+        FAMILY_IPV4 => {
+            let octets: [u8; 4] = xor(address)
+                .try_into()
+                .map_err(|_| eyre!("Invalid IPv4 address in STUN response."))?;
+            Ok(IpAddr::V4(Ipv4Addr::from(octets)))
+        },
+        FAMILY_IPV6 => {
+            let octets: [u8; 16] = xor(address)
+                .try_into()
+                .map_err(|_| eyre!("Invalid IPv6 address in STUN response."))?;
+            Ok(IpAddr::V6(Ipv6Addr::from(octets)))
+        },
+        other => bail!("Unknown address family {other} in STUN response."),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::net::UdpSocket;
+
+    use super::*;
+
+    fn xor_mapped_address(
+        ip_address: IpAddr,
+        transaction_id: [u8; 12],
+    ) -> Vec<u8> {
+        let (family, octets) = match ip_address {
+            IpAddr::V4(ip) => (FAMILY_IPV4, ip.octets().to_vec()),
+            IpAddr::V6(ip) => (FAMILY_IPV6, ip.octets().to_vec()),
+        };
+
+        let mut key = MAGIC_COOKIE.to_be_bytes().to_vec();
+        key.extend(transaction_id);
+
+        let mut value = vec![0, family, 0, 0];
+        value.extend(octets.iter().zip(key).map(|(b, k)| b ^ k));
+        value
+    }
+
+    /// Answers every Binding Request with the address it came from.
+    async fn stand_in_server() -> String {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let address = socket.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let mut buffer = [0; MAX_MESSAGE_SIZE];
+            let (_, peer) = socket.recv_from(&mut buffer).await.unwrap();
+            let transaction_id: [u8; 12] =
+                buffer[8..HEADER_SIZE].try_into().unwrap();
+
+            let value = xor_mapped_address(peer.ip(), transaction_id);
+
+            let mut response = BINDING_SUCCESS.to_be_bytes().to_vec();
+            response
+                .extend(u16::try_from(4 + value.len()).unwrap().to_be_bytes());
+            response.extend(&buffer[4..HEADER_SIZE]);
+            response.extend(ATTR_XOR_MAPPED_ADDRESS.to_be_bytes());
+            response.extend(u16::try_from(value.len()).unwrap().to_be_bytes());
+            response.extend(value);
+
+            socket.send_to(&response, peer).await.unwrap();
+        });
+
+        address.to_string()
+    }
+
+    #[tokio::test]
+    async fn test_query() -> Result<()> {
+        let server = stand_in_server().await;
+
+        let ip_address =
+            query(&server, IpFamily::Ipv4, &PublicIpConfig::default()).await?;
+
+        assert_eq!(ip_address, IpAddr::from(Ipv4Addr::LOCALHOST));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_ipv6_address() -> Result<()> {
+        let transaction_id = [7; 12];
+        let ip_address: IpAddr = "2001:db8::1234".parse()?;
+
+        let value = xor_mapped_address(ip_address, transaction_id);
+
+        assert_eq!(parse_address(&value, Some(transaction_id))?, ip_address);
+        Ok(())
+    }
+}
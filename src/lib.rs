@@ -10,20 +10,28 @@ pub(crate) mod bunny_api;
 pub mod cli;
 pub(crate) mod cloudflare_api;
 pub mod config;
+pub(crate) mod digitalocean_api;
+pub(crate) mod hetzner_api;
 pub mod ip_cache;
+pub mod ip_family;
+pub mod ip_policy;
+pub mod ip_source;
 pub mod provider;
+pub(crate) mod route53_api;
 pub mod state;
+mod xml;
 
-use std::net::Ipv4Addr;
 use std::str::FromStr;
 
-use color_eyre::Result;
+use color_eyre::Report;
 use tracing::Level;
 use tracing::level_filters::LevelFilter;
 use tracing_subscriber::filter::FilterFn;
 use tracing_subscriber::fmt;
 use tracing_subscriber::prelude::*;
 
+use crate::ip_source::IpLookupError;
+
 pub const PKG_NAME: &str = env!("CARGO_PKG_NAME");
 const CRATE_NAME: &str = env!("CARGO_CRATE_NAME");
 
@@ -69,6 +77,18 @@ pub fn init() -> color_eyre::Result<()> {
     Ok(())
 }
 
-pub async fn get_public_ip_address(url: &str) -> Result<Ipv4Addr> {
-    Ok(reqwest::get(url).await?.text().await?.parse()?)
+/// Runs `main`, reporting errors like returning them from `main` would, but
+/// exiting with a status that reflects the kind of error.
+pub async fn run(main: impl Future<Output = color_eyre::Result<()>>) {
+    if let Err(report) = main.await {
+        eprintln!("Error: {report:?}");
+        std::process::exit(exit_code(&report).into());
+    }
+}
+
+/// The exit status for a failed run: specific statuses for failed IP lookups
+/// (see [`IpLookupError::exit_code`]), 1 otherwise.
+#[must_use]
+pub fn exit_code(report: &Report) -> u8 {
+    IpLookupError::find(report).map_or(1, IpLookupError::exit_code)
 }
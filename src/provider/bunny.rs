@@ -1,10 +1,13 @@
 use color_eyre::Result;
+use color_eyre::eyre::eyre;
 use reqwest::Client;
 
 use crate::bunny_api::build_bunny_client;
 use crate::bunny_api::record::{BunnyRecord, list_records, update_record};
 use crate::bunny_api::zone::list_zones as bunny_list_zones;
-use crate::provider::{DnsProvider, DnsRecord, DnsRecordType, Zone};
+use crate::provider::{
+    DnsProvider, DnsRecord, DnsRecordType, StoredRecord, Zone, normalize_name,
+};
 
 pub struct BunnyProvider {
     client: Client,
@@ -16,38 +19,47 @@ impl BunnyProvider {
     }
 }
 
-fn bunny_type_to_u8(t: DnsRecordType) -> u8 {
-    match t {
-        DnsRecordType::A => 0,
-        DnsRecordType::AAAA => 1,
-        DnsRecordType::CNAME => 2,
-        DnsRecordType::TXT => 3,
-        DnsRecordType::MX => 4,
-        DnsRecordType::SRV => 8,
-        DnsRecordType::MISC => 255,
-    }
-}
-
 // Bunny stores record names as bare subdomains ("www") and uses "" or "@" for the zone apex.
-// We normalise to FQDNs on the way in and reverse on the way out so callers see the same
-// name format as Cloudflare.
-
-fn normalize_name(bunny_name: &str, zone_name: &str) -> String {
-    if bunny_name.is_empty() || bunny_name == "@" {
-        zone_name.to_owned()
-    } else {
-        format!("{bunny_name}.{zone_name}")
-    }
+// We normalise to FQDNs on the way in so callers see the same name format as Cloudflare.
+
+/// The names closest to `name`, by edit distance, that are likely to be
+/// what was meant.
+fn close_matches<'a>(
+    name: &str,
+    names: impl Iterator<Item = &'a str>,
+) -> Vec<&'a str> {
+    let max_distance = (name.len() / 4).max(1);
+
+    let mut matches: Vec<_> = names
+        .map(|n| (edit_distance(name, n), n))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    matches.sort_unstable();
+
+    matches.into_iter().take(3).map(|(_, n)| n).collect()
 }
 
-fn raw_subdomain<'a>(record_name: &'a str, zone_name: &str) -> &'a str {
-    if record_name == zone_name {
-        ""
-    } else {
-        record_name
-            .strip_suffix(&format!(".{zone_name}"))
-            .unwrap_or(record_name)
+/// The Levenshtein distance between `a` and `b`, ignoring case.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<_> = a.chars().map(|c| c.to_ascii_lowercase()).collect();
+    let b: Vec<_> = b.chars().map(|c| c.to_ascii_lowercase()).collect();
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            let deletion = previous[j + 1] + 1;
+            let insertion = current[j] + 1;
+            current.push(substitution.min(deletion).min(insertion));
+        }
+
+        previous = current;
     }
+
+    previous[b.len()]
 }
 
 fn map_bunny_record(r: BunnyRecord, zone_name: &str) -> DnsRecord {
@@ -55,23 +67,62 @@ fn map_bunny_record(r: BunnyRecord, zone_name: &str) -> DnsRecord {
         id: r.id.to_string(),
         name: normalize_name(&r.name, zone_name),
         record_type: DnsRecordType::from(r.record_type),
-        content: r.value,
+        raw_type: r.record_type.to_string(),
+        content: r.value.clone(),
         ttl: Some(r.ttl),
+        proxied: None,
+        comment: r.comment.clone(),
+        tags: None,
+        stored: Some(StoredRecord::Bunny(r)),
     }
 }
 
 impl DnsProvider for BunnyProvider {
     async fn list_zones(&self) -> Result<Vec<Zone>> {
-        let zones = bunny_list_zones(&self.client).await?;
+        let zones = bunny_list_zones(&self.client, None).await?;
         Ok(zones
             .into_iter()
             .map(|z| Zone { id: z.id.to_string(), name: z.domain })
             .collect())
     }
 
+    async fn find_zone(&self, zone_name: &str) -> Result<Option<Zone>> {
+        // A zone ID is used as is.
+        if zone_name.parse::<i64>().is_ok() {
+            return Ok(None);
+        }
+
+        let zones = bunny_list_zones(&self.client, Some(zone_name)).await?;
+
+        if let Some(zone) =
+            zones.into_iter().find(|z| z.domain.eq_ignore_ascii_case(zone_name))
+        {
+            let id = zone.id.to_string();
+            return Ok(Some(Zone { id, name: zone.domain }));
+        }
+
+        let zones = self.list_zones().await?;
+        let suggestions =
+            close_matches(zone_name, zones.iter().map(|z| z.name.as_str()));
+
+        if suggestions.is_empty() {
+            Err(eyre!("Zone '{zone_name}' not found on bunny.net."))
+        } else {
+            Err(eyre!(
+                "Zone '{zone_name}' not found on bunny.net. Did you mean {}?",
+                suggestions
+                    .iter()
+                    .map(|s| format!("'{s}'"))
+                    .collect::<Vec<_>>()
+                    .join(" or ")
+            ))
+        }
+    }
+
     async fn list_records(&self, zone: &Zone) -> Result<Vec<DnsRecord>> {
         let zone_id: i64 = zone.id.parse()?;
         let records = list_records(&self.client, zone_id).await?;
+
         Ok(records
             .into_iter()
             .map(|r| map_bunny_record(r, &zone.name))
@@ -83,20 +134,81 @@ impl DnsProvider for BunnyProvider {
         zone: &Zone,
         record: &DnsRecord,
         new_ip: &str,
+    ) -> Result<()> {
+        self.update_records(zone, &[(record.clone(), new_ip.to_owned())]).await
+    }
+
+    async fn update_records(
+        &self,
+        zone: &Zone,
+        updates: &[(DnsRecord, String)],
     ) -> Result<()> {
         let zone_id: i64 = zone.id.parse()?;
-        let record_id: i64 = record.id.parse()?;
-        let raw_name = raw_subdomain(&record.name, &zone.name);
-        let ttl = record.ttl.unwrap_or(300);
-        update_record(
-            &self.client,
-            zone_id,
-            record_id,
-            bunny_type_to_u8(record.record_type),
-            raw_name,
-            ttl,
-            new_ip,
-        )
-        .await
+
+        for (record, new_ip) in updates {
+            let Some(StoredRecord::Bunny(mut stored)) = record.stored.clone()
+            else {
+                return Err(record.not_listed());
+            };
+
+            stored.value.clone_from(new_ip);
+            if let Some(ttl) = record.ttl {
+                stored.ttl = ttl;
+            }
+
+            update_record(&self.client, zone_id, &stored).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_map_bunny_record() -> Result<()> {
+        let types = [
+            (5, DnsRecordType::Redirect),
+            (6, DnsRecordType::Flatten),
+            (7, DnsRecordType::PullZone),
+            (9, DnsRecordType::CAA),
+            (10, DnsRecordType::PTR),
+            (11, DnsRecordType::Script),
+            (12, DnsRecordType::NS),
+        ];
+
+        for (raw_type, record_type) in types {
+            let stored: BunnyRecord = serde_json::from_value(json!({
+                "Id": 1, "Name": "www", "Type": raw_type,
+                "Value": "value", "Ttl": 300,
+            }))?;
+            let record = map_bunny_record(stored, "example.com");
+            assert_eq!(record.record_type, record_type);
+            assert_eq!(record.raw_type, raw_type.to_string());
+
+            // Updates send the type back as bunny listed it.
+            let Some(StoredRecord::Bunny(stored)) = record.stored else {
+                panic!("{raw_type} isn't stored");
+            };
+            assert_eq!(serde_json::to_value(&stored)?["Type"], raw_type);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_close_matches() {
+        let zones = ["example.com", "example.nl", "exemplary.com", "other.org"];
+
+        assert_eq!(close_matches("exmaple.com", zones.into_iter()), [
+            "example.com"
+        ]);
+        assert_eq!(close_matches("example.net", zones.into_iter()), [
+            "example.nl"
+        ]);
+        assert!(close_matches("unrelated.io", zones.into_iter()).is_empty());
     }
 }
@@ -1,18 +1,27 @@
 use color_eyre::Result;
 use color_eyre::eyre::eyre;
+use indexmap::{IndexMap, IndexSet};
 use reqwest::Client;
 use reqwest::header::HeaderMap;
 
-use crate::cloudflare_api::record::{get_records, patch_record};
+use crate::cloudflare_api::record::{
+    BatchPatch, CloudflareRecord, PatchRecordRequest, batch_patch_records,
+    check_edit_access, get_records, patch_record,
+};
+use crate::cloudflare_api::token::{get_user, verify_token};
 use crate::cloudflare_api::zone::list_zones as cf_list_zones;
-use crate::provider::{DnsProvider, DnsRecord, Zone};
+use crate::config::{CloudflareAuth, CloudflareConfig, ZoneConfig};
+use crate::provider::{DnsProvider, DnsRecord, DnsRecordType, Zone};
 
 pub struct CloudflareProvider {
     client: Client,
+    auth: CloudflareAuth,
+    account_id: Option<String>,
 }
 
 impl CloudflareProvider {
-    pub fn new(token: &str) -> Result<Self> {
+    pub fn new(config: &CloudflareConfig) -> Result<Self> {
+        let auth = config.auth();
         let mut headers = HeaderMap::new();
         headers.insert(
             "Content-Type",
@@ -20,39 +29,133 @@ impl CloudflareProvider {
                 .parse()
                 .map_err(|_| eyre!("Invalid Content-Type header"))?,
         );
-        headers.insert(
-            "Authorization",
-            format!("Bearer {token}")
-                .parse()
-                .map_err(|_| eyre!("Invalid Authorization header"))?,
-        );
+        match auth {
+            CloudflareAuth::Token(token) => {
+                headers.insert(
+                    "Authorization",
+                    format!("Bearer {token}")
+                        .parse()
+                        .map_err(|_| eyre!("Invalid Authorization header"))?,
+                );
+            },
+            CloudflareAuth::GlobalKey { email, api_key } => {
+                headers.insert(
+                    "X-Auth-Email",
+                    email
+                        .parse()
+                        .map_err(|_| eyre!("Invalid X-Auth-Email header"))?,
+                );
+                headers.insert(
+                    "X-Auth-Key",
+                    api_key
+                        .parse()
+                        .map_err(|_| eyre!("Invalid X-Auth-Key header"))?,
+                );
+            },
+        }
         let client = Client::builder()
             .default_headers(headers)
             .use_rustls_tls()
             .build()?;
-        Ok(Self { client })
+        Ok(Self {
+            client,
+            auth: auth.clone(),
+            account_id: config.account_id().map(ToOwned::to_owned),
+        })
+    }
+
+    /// The `account.id` filter for zone lookups, if an account is configured.
+    fn account_filter(&self) -> Option<(&str, &str)> {
+        self.account_id.as_deref().map(|id| ("account.id", id))
+    }
+}
+
+fn patch_request<'a>(
+    record: &'a DnsRecord,
+    new_ip: &'a str,
+) -> PatchRecordRequest<'a> {
+    PatchRecordRequest {
+        content: new_ip,
+        ttl: record.ttl,
+        proxied: record.proxied,
+        comment: record.comment.as_deref(),
+        // Tags need a paid plan, so they're only sent if the record has some
+        // or they're configured, which can also clear them.
+        tags: record.tags.as_deref(),
+    }
+}
+
+fn map_cloudflare_record(r: CloudflareRecord) -> DnsRecord {
+    DnsRecord {
+        id: r.id,
+        name: r.name,
+        record_type: r.record_type.parse().unwrap_or(DnsRecordType::MISC),
+        raw_type: r.record_type,
+        content: r.content,
+        ttl: Some(r.ttl),
+        proxied: Some(r.proxied),
+        comment: r.comment,
+        tags: (!r.tags.is_empty()).then_some(r.tags),
+        stored: None,
     }
 }
 
 impl DnsProvider for CloudflareProvider {
     async fn list_zones(&self) -> Result<Vec<Zone>> {
-        let zones = cf_list_zones(&self.client).await?;
+        let filters: Vec<_> = self.account_filter().into_iter().collect();
+        let zones = cf_list_zones(&self.client, &filters).await?;
         Ok(zones.into_iter().map(|z| Zone { id: z.id, name: z.name }).collect())
     }
 
+    async fn find_zone(&self, zone_name: &str) -> Result<Option<Zone>> {
+        let filters: Vec<_> = std::iter::once(("name", zone_name))
+            .chain(self.account_filter())
+            .collect();
+        let mut zones = cf_list_zones(&self.client, &filters).await?;
+
+        if zones.len() > 1 {
+            return Err(eyre!(
+                "Zone '{zone_name}' exists in {} accounts. Set `account_id` in the [cloudflare] section to select one.",
+                zones.len()
+            ));
+        }
+
+        Ok(zones.pop().map(|z| Zone { id: z.id, name: z.name }))
+    }
+
     async fn list_records(&self, zone: &Zone) -> Result<Vec<DnsRecord>> {
-        let records = get_records(&self.client, &zone.id).await?;
+        let records = get_records(&self.client, &zone.id, &[]).await?;
+        Ok(records.into_iter().map(map_cloudflare_record).collect())
+    }
+
+    async fn list_selected_records(
+        &self,
+        zone: &Zone,
+        zone_config: &ZoneConfig,
+    ) -> Result<Vec<DnsRecord>> {
+        let filters: IndexSet<_> = zone_config
+            .records()
+            .iter()
+            .map(|r| (r.name(), r.record_type().to_string()))
+            .collect();
+
+        // A configured name selects the record of that name and the records
+        // below it, so every record starting with the name is a candidate.
+        // Records fetched for several configurations are only kept once.
+        let mut records = IndexMap::new();
+        for (name, record_type) in &filters {
+            let filter =
+                [("type", record_type.as_str()), ("name.startswith", name)];
+
+            for record in get_records(&self.client, &zone.id, &filter).await? {
+                records.entry(record.id.clone()).or_insert(record);
+            }
+        }
+
         Ok(records
-            .into_iter()
-            .map(|r| {
-                DnsRecord {
-                    id: r.id,
-                    name: r.name,
-                    record_type: r.record_type,
-                    content: r.content,
-                    ttl: None,
-                }
-            })
+            .into_values()
+            .map(map_cloudflare_record)
+            .filter(|r| zone_config.is_record_selected(&r.name, r.record_type))
             .collect())
     }
 
@@ -62,6 +165,97 @@ impl DnsProvider for CloudflareProvider {
         record: &DnsRecord,
         new_ip: &str,
     ) -> Result<()> {
-        patch_record(&self.client, &zone.id, &record.id, new_ip).await
+        let request = patch_request(record, new_ip);
+        patch_record(&self.client, &zone.id, &record.id, &request).await
+    }
+
+    async fn update_records(
+        &self,
+        zone: &Zone,
+        updates: &[(DnsRecord, String)],
+    ) -> Result<()> {
+        let patches: Vec<_> = updates
+            .iter()
+            .map(|(record, new_ip)| {
+                BatchPatch {
+                    id: &record.id,
+                    patch: patch_request(record, new_ip),
+                }
+            })
+            .collect();
+
+        batch_patch_records(&self.client, &zone.id, &patches).await
+    }
+
+    async fn verify_credentials(&self) -> Result<String> {
+        if let CloudflareAuth::GlobalKey { email, .. } = &self.auth {
+            // Only API tokens can be verified, so check that the key gives
+            // access to the account.
+            get_user(&self.client).await?;
+            return Ok(format!("global API key of {email}"));
+        }
+
+        let token = verify_token(&self.client).await?;
+
+        if token.status != "active" {
+            return Err(eyre!("Token '{}' is {}.", token.id, token.status));
+        }
+
+        Ok(match token.expires_on {
+            Some(expires_on) => format!("token active, expires {expires_on}"),
+            None => "token active".to_owned(),
+        })
+    }
+
+    async fn verify_edit_access(&self, zone: &Zone) -> Result<bool> {
+        check_edit_access(&self.client, &zone.id).await?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn cloudflare_record(record_type: &str, content: &str) -> CloudflareRecord {
+        CloudflareRecord {
+            id: "id".to_owned(),
+            name: "example.com".to_owned(),
+            record_type: record_type.to_owned(),
+            content: content.to_owned(),
+            ttl: 1,
+            proxied: false,
+            comment: None,
+            tags: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_map_cloudflare_record() {
+        let record =
+            map_cloudflare_record(cloudflare_record("A", "203.0.113.7"));
+        assert_eq!(record.record_type, DnsRecordType::A);
+        assert_eq!(record.raw_type, "A");
+
+        // Types without a variant of their own keep the type as listed.
+        let record = map_cloudflare_record(cloudflare_record(
+            "HTTPS",
+            "1 . alpn=\"h2\"",
+        ));
+        assert_eq!(record.record_type, DnsRecordType::MISC);
+        assert_eq!(record.raw_type, "HTTPS");
+        assert_eq!(record.content, "1 . alpn=\"h2\"");
+    }
+
+    #[test]
+    fn test_patch_request() {
+        let mut record =
+            map_cloudflare_record(cloudflare_record("A", "203.0.113.7"));
+        assert_eq!(patch_request(&record, "203.0.113.8").tags, None);
+
+        // Configured empty tags clear the tags on the record.
+        record.tags = Some(Vec::new());
+        let request = patch_request(&record, "203.0.113.8");
+        assert_eq!(request.tags, Some(&[][..]));
     }
 }
@@ -0,0 +1,79 @@
+use color_eyre::Result;
+use reqwest::Client;
+
+use crate::digitalocean_api::build_digitalocean_client;
+use crate::digitalocean_api::domain::{get_domain, list_domains};
+use crate::digitalocean_api::record::{
+    DigitalOceanRecord, PatchRecordRequest, list_records, patch_record,
+};
+use crate::provider::{
+    DnsProvider, DnsRecord, DnsRecordType, Zone, normalize_name,
+};
+
+pub struct DigitalOceanProvider {
+    client: Client,
+}
+
+impl DigitalOceanProvider {
+    pub fn new(token: &str) -> Result<Self> {
+        Ok(Self { client: build_digitalocean_client(token)? })
+    }
+}
+
+fn map_digitalocean_record(
+    r: DigitalOceanRecord,
+    zone_name: &str,
+) -> DnsRecord {
+    DnsRecord {
+        id: r.id.to_string(),
+        name: normalize_name(&r.name, zone_name),
+        record_type: r.record_type.parse().unwrap_or(DnsRecordType::MISC),
+        raw_type: r.record_type,
+        content: r.data,
+        ttl: r.ttl,
+        proxied: None,
+        comment: None,
+        tags: None,
+        stored: None,
+    }
+}
+
+// Domains have no ID of their own, so the domain name doubles as the zone ID.
+
+impl DnsProvider for DigitalOceanProvider {
+    async fn list_zones(&self) -> Result<Vec<Zone>> {
+        let domains = list_domains(&self.client).await?;
+        Ok(domains
+            .into_iter()
+            .map(|d| Zone { id: d.name.clone(), name: d.name })
+            .collect())
+    }
+
+    async fn find_zone(&self, zone_name: &str) -> Result<Option<Zone>> {
+        let domain = get_domain(&self.client, zone_name).await?;
+        Ok(domain.map(|d| Zone { id: d.name.clone(), name: d.name }))
+    }
+
+    async fn list_records(&self, zone: &Zone) -> Result<Vec<DnsRecord>> {
+        let records = list_records(&self.client, &zone.id).await?;
+        Ok(records
+            .into_iter()
+            .map(|r| map_digitalocean_record(r, &zone.name))
+            .collect())
+    }
+
+    async fn update_record(
+        &self,
+        zone: &Zone,
+        record: &DnsRecord,
+        new_ip: &str,
+    ) -> Result<()> {
+        let request = PatchRecordRequest {
+            record_type: &record.raw_type,
+            data: new_ip,
+            ttl: record.ttl,
+        };
+
+        patch_record(&self.client, &zone.id, &record.id, &request).await
+    }
+}
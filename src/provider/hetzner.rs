@@ -0,0 +1,77 @@
+use color_eyre::Result;
+use reqwest::Client;
+
+use crate::hetzner_api::build_hetzner_client;
+use crate::hetzner_api::record::{HetznerRecord, list_records, update_record};
+use crate::hetzner_api::zone::list_zones as hetzner_list_zones;
+use crate::provider::{
+    DnsProvider, DnsRecord, DnsRecordType, StoredRecord, Zone, normalize_name,
+};
+
+pub struct HetznerProvider {
+    client: Client,
+}
+
+impl HetznerProvider {
+    pub fn new(token: &str) -> Result<Self> {
+        Ok(Self { client: build_hetzner_client(token)? })
+    }
+}
+
+fn map_hetzner_record(r: HetznerRecord, zone_name: &str) -> DnsRecord {
+    DnsRecord {
+        id: r.id.clone(),
+        name: normalize_name(&r.name, zone_name),
+        record_type: r.record_type.parse().unwrap_or(DnsRecordType::MISC),
+        raw_type: r.record_type.clone(),
+        content: r.value.clone(),
+        ttl: r.ttl,
+        proxied: None,
+        comment: None,
+        tags: None,
+        stored: Some(StoredRecord::Hetzner(r)),
+    }
+}
+
+impl DnsProvider for HetznerProvider {
+    async fn list_zones(&self) -> Result<Vec<Zone>> {
+        let zones = hetzner_list_zones(&self.client, None).await?;
+        Ok(zones.into_iter().map(|z| Zone { id: z.id, name: z.name }).collect())
+    }
+
+    async fn find_zone(&self, zone_name: &str) -> Result<Option<Zone>> {
+        let zones = hetzner_list_zones(&self.client, Some(zone_name)).await?;
+        Ok(zones
+            .into_iter()
+            .find(|z| z.name == zone_name)
+            .map(|z| Zone { id: z.id, name: z.name }))
+    }
+
+    async fn list_records(&self, zone: &Zone) -> Result<Vec<DnsRecord>> {
+        let records = list_records(&self.client, &zone.id).await?;
+
+        Ok(records
+            .into_iter()
+            .map(|r| map_hetzner_record(r, &zone.name))
+            .collect())
+    }
+
+    async fn update_record(
+        &self,
+        _zone: &Zone,
+        record: &DnsRecord,
+        new_ip: &str,
+    ) -> Result<()> {
+        let Some(StoredRecord::Hetzner(mut stored)) = record.stored.clone()
+        else {
+            return Err(record.not_listed());
+        };
+
+        stored.value = new_ip.to_owned();
+        if record.ttl.is_some() {
+            stored.ttl = record.ttl;
+        }
+
+        update_record(&self.client, &stored).await
+    }
+}
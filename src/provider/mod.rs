@@ -1,26 +1,54 @@
-//! Provider abstraction over Cloudflare and bunny.net DNS APIs.
+//! Provider abstraction over the DNS APIs of Cloudflare, bunny.net, Hetzner, DigitalOcean and
+//! Route 53.
 //!
-//! Both providers are wrapped behind [`DnsProvider`] and use the same [`DnsRecord`] and [`Zone`]
-//! types. The differences between the two APIs are handled in each provider module:
+//! Every provider is wrapped behind [`DnsProvider`] and uses the same [`DnsRecord`] and [`Zone`]
+//! types. The differences between the APIs are handled in each provider module:
 //!
-//! | Aspect          | Cloudflare                            | bunny.net                                 |
-//! |-----------------|---------------------------------------|-------------------------------------------|
-//! | Auth header     | `Authorization: Bearer {token}`       | `AccessKey: {token}`                      |
-//! | Record name     | FQDN (`www.example.com`)              | Bare subdomain (`www`); apex as `""` or `"@"` |
-//! | Record type     | String (`"A"`, `"AAAA"`, …)           | Integer (`0`=A, `1`=AAAA, `2`=CNAME, `3`=TXT, `4`=MX, `8`=SRV) |
-//! | Update method   | `PATCH` with changed fields only      | `POST` with full record body incl. TTL    |
-//! | Zone listing    | Cursor-based pagination               | Page/perPage with `HasMoreItems` flag     |
+//! | Provider     | Auth header                     | Record name                     | Record type                          | Update method                         | Pagination                           |
+//! |--------------|---------------------------------|---------------------------------|--------------------------------------|---------------------------------------|--------------------------------------|
+//! | Cloudflare   | `Authorization: Bearer {token}` | FQDN (`www.example.com`)        | String (`"A"`, `"AAAA"`, …)          | Batched `PATCH`es, applied atomically | `page`/`per_page` with `result_info` |
+//! | bunny.net    | `AccessKey: {token}`            | Relative (`www`); apex `""`/`@` | Integer (`0`=A, `1`=AAAA, … `12`=NS) | `POST` of the full stored record      | `page`/`perPage` with `HasMoreItems` |
+//! | Hetzner      | `Auth-API-Token: {token}`       | Relative (`www`); apex `@`      | String (`"A"`, `"AAAA"`, …)          | `PUT` of the full stored record       | `page`/`per_page` with `meta`        |
+//! | DigitalOcean | `Authorization: Bearer {token}` | Relative (`www`); apex `@`      | String (`"A"`, `"AAAA"`, …)          | `PATCH` with changed fields only      | Follows `links.pages.next`           |
+//! | Route 53     | Signature Version 4             | FQDN with trailing dot          | String (`"A"`, `"AAAA"`, …)          | `UPSERT`s of the full stored set      | `IsTruncated` with `Next…` markers   |
 //!
-//! **Name normalisation**: to give callers a consistent view, `BunnyProvider::list_records`
-//! converts bare subdomain names to FQDNs (`www` → `www.example.com`, `""` → `example.com`).
-//! `BunnyProvider::update_record` reverses this before calling the API.
+//! **Name normalisation**: to give callers a consistent view, providers with relative record
+//! names convert them to FQDNs when listing records (`www` → `www.example.com`, `@` →
+//! `example.com`). Updates either patch only the value and TTL, or send back the record as the
+//! provider stores it with only those changed, so the name and every other attribute are kept.
+//!
+//! **Record types**: [`DnsRecord::raw_type`] keeps the type exactly as the provider reported it,
+//! so a record is always written back with the type it was read with, including types that
+//! [`DnsRecordType`] has no variant for.
+
+#![allow(clippy::doc_markdown)]
 
 pub mod bunny;
 pub mod cloudflare;
+pub mod digitalocean;
+pub mod hetzner;
+pub mod route53;
 
-use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use color_eyre::{Report, Result};
 use serde::Deserialize;
 use strum_macros::{Display, EnumString};
+use tracing::{debug, warn};
+
+use crate::bunny_api::record::BunnyRecord;
+use crate::config::ZoneConfig;
+use crate::hetzner_api::record::HetznerRecord;
+use crate::route53_api::record::ResourceRecordSet;
+
+/// The FQDN of a record named relative to its zone, where `""` and `"@"` are
+/// the zone apex.
+pub(crate) fn normalize_name(relative_name: &str, zone_name: &str) -> String {
+    if relative_name.is_empty() || relative_name == "@" {
+        zone_name.to_owned()
+    } else {
+        format!("{relative_name}.{zone_name}")
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct Zone {
@@ -33,8 +61,35 @@ pub struct DnsRecord {
     pub id: String,
     pub name: String,
     pub record_type: DnsRecordType,
+    /// The type as the provider represents it, e.g. `"CAA"` for Cloudflare
+    /// or `"9"` for bunny.net.
+    pub raw_type: String,
     pub content: String,
     pub ttl: Option<u32>,
+    /// Whether traffic is proxied through the provider, if it supports that.
+    pub proxied: Option<bool>,
+    pub comment: Option<String>,
+    /// The tags on the record, or `None` if it has none.
+    pub tags: Option<Vec<String>>,
+    /// The record as the provider listed it, for providers whose updates
+    /// send the whole record back.
+    pub stored: Option<StoredRecord>,
+}
+
+impl DnsRecord {
+    /// The error for updating a record that wasn't listed with its stored
+    /// record.
+    pub(crate) fn not_listed(&self) -> Report {
+        eyre!("Record '{}' wasn't listed from its zone.", self.name)
+    }
+}
+
+/// A record as a provider stores it.
+#[derive(Debug, Clone)]
+pub enum StoredRecord {
+    Bunny(BunnyRecord),
+    Hetzner(HetznerRecord),
+    Route53(ResourceRecordSet),
 }
 
 #[derive(
@@ -48,6 +103,17 @@ pub enum DnsRecordType {
     TXT,
     SRV,
     CNAME,
+    CAA,
+    PTR,
+    NS,
+    /// bunny.net: an HTTP redirect.
+    Redirect,
+    /// bunny.net: a flattened CNAME at the zone apex.
+    Flatten,
+    /// bunny.net: an alias of a pull zone.
+    PullZone,
+    /// bunny.net: a record answered by a scriptable DNS script.
+    Script,
     #[serde(other)]
     MISC,
 }
@@ -60,7 +126,14 @@ impl From<u8> for DnsRecordType {
             2 => Self::CNAME,
             3 => Self::TXT,
             4 => Self::MX,
+            5 => Self::Redirect,
+            6 => Self::Flatten,
+            7 => Self::PullZone,
             8 => Self::SRV,
+            9 => Self::CAA,
+            10 => Self::PTR,
+            11 => Self::Script,
+            12 => Self::NS,
             _ => Self::MISC,
         }
     }
@@ -70,10 +143,80 @@ impl From<u8> for DnsRecordType {
 pub trait DnsProvider {
     async fn list_zones(&self) -> Result<Vec<Zone>>;
     async fn list_records(&self, zone: &Zone) -> Result<Vec<DnsRecord>>;
+
+    /// The zone named `zone_name`, if there is one. Providers that can look
+    /// zones up by name override this to avoid listing every zone.
+    async fn find_zone(&self, zone_name: &str) -> Result<Option<Zone>> {
+        let zones = self.list_zones().await?;
+        Ok(zones.into_iter().find(|z| z.name == zone_name))
+    }
+
+    /// The zone of `zone_config`, whose name may also be a zone ID.
+    async fn find_configured_zone(
+        &self,
+        zone_config: &ZoneConfig,
+    ) -> Result<Zone> {
+        let zone = self.find_zone(&zone_config.name).await?;
+        debug!("Found zone for '{}': {zone:?}", zone_config.name);
+
+        Ok(zone.unwrap_or_else(|| {
+            warn!(
+                "Zone '{}' not found by name — treating as zone ID directly",
+                zone_config.name
+            );
+            Zone {
+                id: zone_config.name.clone(),
+                name: zone_config.name.clone(),
+            }
+        }))
+    }
+
+    /// The records of `zone` selected by `zone_config`. Providers that can
+    /// filter records server-side override this to fetch fewer records.
+    async fn list_selected_records(
+        &self,
+        zone: &Zone,
+        zone_config: &ZoneConfig,
+    ) -> Result<Vec<DnsRecord>> {
+        let records = self.list_records(zone).await?;
+        Ok(records
+            .into_iter()
+            .filter(|r| zone_config.is_record_selected(&r.name, r.record_type))
+            .collect())
+    }
+
     async fn update_record(
         &self,
         zone: &Zone,
         record: &DnsRecord,
         new_ip: &str,
     ) -> Result<()>;
+
+    /// Points each record to its new address. Providers that support it
+    /// apply all updates at once, so either all of them succeed or none do;
+    /// others update the records one by one.
+    async fn update_records(
+        &self,
+        zone: &Zone,
+        updates: &[(DnsRecord, String)],
+    ) -> Result<()> {
+        for (record, new_ip) in updates {
+            self.update_record(zone, record, new_ip).await?;
+        }
+        Ok(())
+    }
+
+    /// Checks that the credentials are valid, returning a short description
+    /// of them.
+    async fn verify_credentials(&self) -> Result<String> {
+        let zones = self.list_zones().await?;
+        Ok(format!("access to {} zones", zones.len()))
+    }
+
+    /// Checks that the records of `zone` can be edited, without changing
+    /// any, returning whether it was checked. Providers that can't check this
+    /// without changing a record keep the default, which checks nothing.
+    async fn verify_edit_access(&self, _zone: &Zone) -> Result<bool> {
+        Ok(false)
+    }
 }
@@ -0,0 +1,365 @@
+use std::time::{Duration, Instant};
+
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use tokio::time::sleep;
+
+use crate::config::Route53Config;
+use crate::provider::{
+    DnsProvider, DnsRecord, DnsRecordType, StoredRecord, Zone,
+};
+use crate::route53_api::Route53Client;
+use crate::route53_api::credentials::Credentials;
+use crate::route53_api::record::{
+    ResourceRecordSet, get_change_status, list_record_sets, upsert_record_sets,
+};
+use crate::route53_api::zone::{find_hosted_zone, list_hosted_zones};
+
+const SYNC_POLL_INTERVAL: Duration = Duration::from_secs(5);
+const SYNC_TIMEOUT: Duration = Duration::from_mins(3);
+
+pub struct Route53Provider {
+    client: Route53Client,
+    wait_for_sync: bool,
+}
+
+impl Route53Provider {
+    pub fn new(config: &Route53Config) -> Result<Self> {
+        let credentials = Credentials::resolve(
+            config.access_key().map(Credentials::from),
+            config.profile(),
+        )?;
+
+        Ok(Self {
+            client: Route53Client::new(config.endpoint(), credentials)?,
+            wait_for_sync: config.wait_for_sync(),
+        })
+    }
+
+    /// Waits until the change has reached every Route 53 name server.
+    async fn wait_for_change(&self, change_id: &str) -> Result<()> {
+        let started = Instant::now();
+
+        loop {
+            let status = get_change_status(&self.client, change_id).await?;
+            if status == "INSYNC" {
+                return Ok(());
+            }
+
+            if started.elapsed() >= SYNC_TIMEOUT {
+                return Err(eyre!(
+                    "Route 53 change '{change_id}' is still {status} after {} \
+                     seconds.",
+                    SYNC_TIMEOUT.as_secs()
+                ));
+            }
+
+            sleep(SYNC_POLL_INTERVAL).await;
+        }
+    }
+}
+
+// A record set is identified by its name, type and, for sets with a routing
+// policy, set identifier; the record ID joins them. A set with several values
+// is listed as one record, which an update points to the new address alone.
+
+fn record_id(r: &ResourceRecordSet) -> String {
+    let mut id = format!("{}|{}", r.name, r.record_type);
+    if let Some(set_identifier) = &r.set_identifier {
+        id.push('|');
+        id.push_str(set_identifier);
+    }
+    id
+}
+
+fn map_route53_record(r: ResourceRecordSet) -> DnsRecord {
+    // Alias sets point to another name rather than to addresses, so they
+    // can't be updated and get no record type of their own.
+    let (record_type, content) = match &r.alias_target {
+        Some(target) => (DnsRecordType::MISC, format!("alias to {target}")),
+        None => {
+            (
+                r.record_type.parse().unwrap_or(DnsRecordType::MISC),
+                r.values.join(", "),
+            )
+        },
+    };
+
+    DnsRecord {
+        id: record_id(&r),
+        name: r.decoded_name(),
+        record_type,
+        raw_type: r.record_type.clone(),
+        content,
+        ttl: r.ttl,
+        proxied: None,
+        comment: None,
+        tags: None,
+        stored: Some(StoredRecord::Route53(r)),
+    }
+}
+
+fn map_hosted_zone(zone: crate::route53_api::zone::HostedZone) -> Zone {
+    Zone { id: zone.id, name: zone.name }
+}
+
+impl DnsProvider for Route53Provider {
+    async fn list_zones(&self) -> Result<Vec<Zone>> {
+        let zones = list_hosted_zones(&self.client).await?;
+        Ok(zones.into_iter().map(map_hosted_zone).collect())
+    }
+
+    async fn find_zone(&self, zone_name: &str) -> Result<Option<Zone>> {
+        let zone = find_hosted_zone(&self.client, zone_name).await?;
+        Ok(zone.map(map_hosted_zone))
+    }
+
+    async fn list_records(&self, zone: &Zone) -> Result<Vec<DnsRecord>> {
+        let record_sets = list_record_sets(&self.client, &zone.id).await?;
+
+        Ok(record_sets.into_iter().map(map_route53_record).collect())
+    }
+
+    async fn update_record(
+        &self,
+        zone: &Zone,
+        record: &DnsRecord,
+        new_ip: &str,
+    ) -> Result<()> {
+        self.update_records(zone, &[(record.clone(), new_ip.to_owned())]).await
+    }
+
+    async fn update_records(
+        &self,
+        zone: &Zone,
+        updates: &[(DnsRecord, String)],
+    ) -> Result<()> {
+        let mut record_sets = Vec::with_capacity(updates.len());
+
+        for (record, new_ip) in updates {
+            let Some(StoredRecord::Route53(mut stored)) = record.stored.clone()
+            else {
+                return Err(record.not_listed());
+            };
+
+            stored.set_value(new_ip, record.ttl)?;
+            record_sets.push(stored);
+        }
+
+        let change_id =
+            upsert_record_sets(&self.client, &zone.id, &record_sets).await?;
+
+        if self.wait_for_sync {
+            self.wait_for_change(&change_id).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    use super::*;
+
+    struct StandInRequest {
+        method: String,
+        target: String,
+        authorization: String,
+        body: String,
+    }
+
+    const HOSTED_ZONES: &str = "<ListHostedZonesByNameResponse><HostedZones>\
+        <HostedZone><Id>/hostedzone/Z1</Id><Name>example.com.</Name>\
+        <Config><PrivateZone>false</PrivateZone></Config></HostedZone>\
+        </HostedZones><IsTruncated>false</IsTruncated>\
+        </ListHostedZonesByNameResponse>";
+
+    const APEX_SET: &str = "<ListResourceRecordSetsResponse>\
+        <ResourceRecordSets><ResourceRecordSet><Name>example.com.</Name>\
+        <Type>A</Type><AliasTarget><HostedZoneId>Z2</HostedZoneId>\
+        <DNSName>lb.example.net.</DNSName>\
+        <EvaluateTargetHealth>false</EvaluateTargetHealth></AliasTarget>\
+        </ResourceRecordSet></ResourceRecordSets>\
+        <IsTruncated>true</IsTruncated>\
+        <NextRecordName>www.example.com.</NextRecordName>\
+        <NextRecordType>A</NextRecordType>\
+        <NextRecordIdentifier>home</NextRecordIdentifier>\
+        </ListResourceRecordSetsResponse>";
+
+    const WWW_SET: &str = "<ListResourceRecordSetsResponse>\
+        <ResourceRecordSets><ResourceRecordSet><Name>www.example.com.</Name>\
+        <Type>A</Type><SetIdentifier>home</SetIdentifier><Weight>10</Weight>\
+        <TTL>300</TTL><ResourceRecords><ResourceRecord>\
+        <Value>198.51.100.1</Value></ResourceRecord></ResourceRecords>\
+        </ResourceRecordSet></ResourceRecordSets>\
+        <IsTruncated>false</IsTruncated></ListResourceRecordSetsResponse>";
+
+    const CHANGE: &str = "<ChangeResourceRecordSetsResponse><ChangeInfo>\
+        <Id>/change/C1</Id><Status>PENDING</Status></ChangeInfo>\
+        </ChangeResourceRecordSetsResponse>";
+
+    const CHANGE_IN_SYNC: &str = "<GetChangeResponse><ChangeInfo>\
+        <Id>/change/C1</Id><Status>INSYNC</Status></ChangeInfo>\
+        </GetChangeResponse>";
+
+    /// A local stand-in for the Route 53 API, returning its endpoint and the
+    /// requests it receives.
+    fn stand_in() -> (String, Arc<Mutex<Vec<StandInRequest>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let received = Arc::clone(&requests);
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(&stream);
+
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut parts = request_line.split_whitespace();
+                let method = parts.next().unwrap().to_owned();
+                let target = parts.next().unwrap().to_owned();
+
+                let mut authorization = String::new();
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let Some((name, value)) = line.trim().split_once(": ")
+                    else {
+                        break;
+                    };
+                    match name.to_ascii_lowercase().as_str() {
+                        "authorization" => value.clone_into(&mut authorization),
+                        "content-length" => {
+                            content_length = value.parse().unwrap();
+                        },
+                        _ => {},
+                    }
+                }
+
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+
+                let path = target.split('?').next().unwrap();
+                let response = match (method.as_str(), path) {
+                    (_, "/2013-04-01/hostedzonesbyname") => HOSTED_ZONES,
+                    ("GET", "/2013-04-01/hostedzone/Z1/rrset")
+                        if target.contains("startrecordname") =>
+                    {
+                        WWW_SET
+                    },
+                    ("GET", "/2013-04-01/hostedzone/Z1/rrset") => APEX_SET,
+                    ("POST", "/2013-04-01/hostedzone/Z1/rrset") => CHANGE,
+                    (_, "/2013-04-01/change/C1") => CHANGE_IN_SYNC,
+                    _ => "",
+                };
+
+                received.lock().unwrap().push(StandInRequest {
+                    method,
+                    target,
+                    authorization,
+                    body: String::from_utf8(body).unwrap(),
+                });
+
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\
+                     Connection: close\r\n\r\n{response}",
+                    response.len()
+                )
+                .unwrap();
+            }
+        });
+
+        (endpoint, requests)
+    }
+
+    #[test]
+    fn test_record_id() {
+        let xml = "<ListResourceRecordSetsResponse><ResourceRecordSets>\
+            <ResourceRecordSet><Name>example.com.</Name><Type>A</Type>\
+            <AliasTarget><HostedZoneId>Z2</HostedZoneId>\
+            <DNSName>lb.example.net.</DNSName>\
+            <EvaluateTargetHealth>false</EvaluateTargetHealth></AliasTarget>\
+            </ResourceRecordSet><ResourceRecordSet>\
+            <Name>\\052.example.com.</Name><Type>A</Type>\
+            <SetIdentifier>home</SetIdentifier><Weight>10</Weight>\
+            <TTL>300</TTL><ResourceRecords><ResourceRecord>\
+            <Value>198.51.100.1</Value></ResourceRecord></ResourceRecords>\
+            </ResourceRecordSet></ResourceRecordSets>\
+            <IsTruncated>false</IsTruncated></ListResourceRecordSetsResponse>";
+
+        let records: Vec<_> = crate::xml::elements(xml, "ResourceRecordSet")
+            .filter_map(ResourceRecordSet::parse)
+            .map(map_route53_record)
+            .collect();
+
+        assert_eq!(records[0].record_type, DnsRecordType::MISC);
+        assert_eq!(records[0].content, "alias to lb.example.net.");
+        assert_eq!(records[0].id, "example.com.|A");
+
+        assert_eq!(records[1].name, "*.example.com");
+        assert_eq!(records[1].record_type, DnsRecordType::A);
+        assert_eq!(records[1].id, "\\052.example.com.|A|home");
+    }
+
+    #[tokio::test]
+    async fn test_update_records() -> Result<()> {
+        let (endpoint, requests) = stand_in();
+        let config: Route53Config = toml::from_str(&format!(
+            r#"
+            access_key_id = "AKIDEXAMPLE"
+            secret_access_key = "secret"
+            endpoint = "{endpoint}"
+            wait_for_sync = true
+            zones = []
+            "#
+        ))?;
+        let provider = Route53Provider::new(&config)?;
+
+        let zone = provider.find_zone("example.com").await?.unwrap();
+        assert_eq!(zone.id, "Z1");
+
+        let records = provider.list_records(&zone).await?;
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].record_type, DnsRecordType::MISC);
+        assert_eq!(records[0].content, "alias to lb.example.net.");
+
+        let www = &records[1];
+        assert_eq!(www.name, "www.example.com");
+        assert_eq!(www.record_type, DnsRecordType::A);
+        assert_eq!(www.content, "198.51.100.1");
+
+        let mut update = www.clone();
+        update.ttl = Some(60);
+        provider
+            .update_records(&zone, &[(update, "203.0.113.7".to_owned())])
+            .await?;
+
+        let requests = requests.lock().unwrap();
+        assert!(requests.iter().all(|r| {
+            r.authorization
+                .starts_with("AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/")
+        }));
+
+        let change = requests.iter().find(|r| r.method == "POST").unwrap();
+        assert!(change.body.contains(
+            "<Change><Action>UPSERT</Action><ResourceRecordSet>\
+             <Name>www.example.com.</Name><Type>A</Type>\
+             <SetIdentifier>home</SetIdentifier><Weight>10</Weight>\
+             <TTL>60</TTL><ResourceRecords><ResourceRecord>\
+             <Value>203.0.113.7</Value></ResourceRecord></ResourceRecords>\
+             </ResourceRecordSet></Change>"
+        ));
+
+        assert_eq!(requests.last().unwrap().target, "/2013-04-01/change/C1");
+        Ok(())
+    }
+}
@@ -0,0 +1,173 @@
+use std::env;
+use std::path::PathBuf;
+
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+
+use crate::config::AwsAccessKey;
+
+const DEFAULT_PROFILE: &str = "default";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// For temporary credentials.
+    pub session_token: Option<String>,
+}
+
+impl Credentials {
+    /// The credentials to sign requests with: `configured` if given, then the
+    /// `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN`
+    /// environment variables, then `profile` (or `AWS_PROFILE`, or
+    /// `default`) from the shared credentials file. The environment
+    /// variables are skipped when a profile is given.
+    pub fn resolve(
+        configured: Option<Credentials>,
+        profile: Option<&str>,
+    ) -> Result<Self> {
+        if let Some(credentials) = configured {
+            return Ok(credentials);
+        }
+
+        if profile.is_none()
+            && let Some(credentials) = from_env()
+        {
+            return Ok(credentials);
+        }
+
+        let profile = profile
+            .map(str::to_owned)
+            .or_else(|| var("AWS_PROFILE"))
+            .unwrap_or(DEFAULT_PROFILE.to_owned());
+
+        let path = shared_credentials_file().ok_or(eyre!(
+            "No AWS credentials configured, in the environment or in a \
+             shared credentials file."
+        ))?;
+
+        let contents = fs_err::read_to_string(&path)?;
+
+        parse_profile(&contents, &profile).ok_or(eyre!(
+            "Profile '{profile}' in {} has no access key.",
+            path.display()
+        ))
+    }
+}
+
+impl From<&AwsAccessKey> for Credentials {
+    fn from(key: &AwsAccessKey) -> Self {
+        Self {
+            access_key_id: key.access_key_id.clone(),
+            secret_access_key: key.secret_access_key.clone(),
+            session_token: key.session_token.clone(),
+        }
+    }
+}
+
+fn var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+fn from_env() -> Option<Credentials> {
+    Some(Credentials {
+        access_key_id: var("AWS_ACCESS_KEY_ID")?,
+        secret_access_key: var("AWS_SECRET_ACCESS_KEY")?,
+        session_token: var("AWS_SESSION_TOKEN"),
+    })
+}
+
+/// `AWS_SHARED_CREDENTIALS_FILE`, or `~/.aws/credentials` if it exists.
+fn shared_credentials_file() -> Option<PathBuf> {
+    if let Some(path) = var("AWS_SHARED_CREDENTIALS_FILE") {
+        return Some(path.into());
+    }
+
+    let path = dirs::home_dir()?.join(".aws").join("credentials");
+    path.exists().then_some(path)
+}
+
+/// The credentials of `profile` in a shared credentials file, which is INI
+/// with a `[profile]` section per profile.
+fn parse_profile(contents: &str, profile: &str) -> Option<Credentials> {
+    let mut in_profile = false;
+    let mut access_key_id = None;
+    let mut secret_access_key = None;
+    let mut session_token = None;
+
+    for line in contents.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with(['#', ';']) {
+            continue;
+        }
+
+        if let Some(section) =
+            line.strip_prefix('[').and_then(|l| l.strip_suffix(']'))
+        {
+            in_profile = section.trim() == profile;
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+
+        if in_profile {
+            let value = Some(value.trim().to_owned());
+            match key.trim() {
+                "aws_access_key_id" => access_key_id = value,
+                "aws_secret_access_key" => secret_access_key = value,
+                "aws_session_token" => session_token = value,
+                _ => {},
+            }
+        }
+    }
+
+    Some(Credentials {
+        access_key_id: access_key_id?,
+        secret_access_key: secret_access_key?,
+        session_token,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_profile() {
+        let contents = "
+            [default]
+            aws_access_key_id = AKIDDEFAULT
+            aws_secret_access_key = default/secret
+
+            # Temporary credentials
+            [ryndns]
+            aws_access_key_id=AKIDRYNDNS
+            aws_secret_access_key=ryndns/secret
+            aws_session_token = token=
+            region = eu-west-1
+
+            [incomplete]
+            aws_access_key_id = AKIDINCOMPLETE
+        ";
+
+        assert_eq!(
+            parse_profile(contents, "default"),
+            Some(Credentials {
+                access_key_id: "AKIDDEFAULT".to_owned(),
+                secret_access_key: "default/secret".to_owned(),
+                session_token: None,
+            })
+        );
+        assert_eq!(
+            parse_profile(contents, "ryndns"),
+            Some(Credentials {
+                access_key_id: "AKIDRYNDNS".to_owned(),
+                secret_access_key: "ryndns/secret".to_owned(),
+                session_token: Some("token=".to_owned()),
+            })
+        );
+        assert_eq!(parse_profile(contents, "incomplete"), None);
+        assert_eq!(parse_profile(contents, "missing"), None);
+    }
+}
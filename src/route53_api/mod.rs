@@ -0,0 +1,141 @@
+pub mod credentials;
+pub mod record;
+mod sigv4;
+pub mod zone;
+
+use std::time::SystemTime;
+
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use reqwest::{Method, Url};
+
+use self::credentials::Credentials;
+use self::sigv4::{Request, Scope, encode_query, sign};
+use crate::xml;
+
+const ROUTE53_API_URL: &str = "https://route53.amazonaws.com";
+const ROUTE53_API_VERSION: &str = "2013-04-01";
+
+/// Route 53 is global, but requests are signed for `us-east-1`.
+const SCOPE: Scope<'static> = Scope { region: "us-east-1", service: "route53" };
+
+/// A client that signs every request with Signature Version 4.
+pub struct Route53Client {
+    client: reqwest::Client,
+    endpoint: String,
+    credentials: Credentials,
+}
+
+impl Route53Client {
+    /// A client for `endpoint`, which defaults to the Route 53 API.
+    pub fn new(
+        endpoint: Option<&str>,
+        credentials: Credentials,
+    ) -> Result<Self> {
+        Ok(Self {
+            client: reqwest::Client::builder().use_rustls_tls().build()?,
+            endpoint: endpoint
+                .unwrap_or(ROUTE53_API_URL)
+                .trim_end_matches('/')
+                .to_owned(),
+            credentials,
+        })
+    }
+
+    /// Sends a signed request to `path`, relative to the API version,
+    /// returning the XML response.
+    async fn send(
+        &self,
+        operation: &str,
+        method: Method,
+        path: &str,
+        query: &[(&str, &str)],
+        body: String,
+    ) -> Result<String> {
+        let query = encode_query(query);
+        let mut url = Url::parse(&format!(
+            "{}/{ROUTE53_API_VERSION}{path}",
+            self.endpoint
+        ))?;
+        if !query.is_empty() {
+            url.set_query(Some(&query));
+        }
+
+        let host = url.host_str().ok_or(eyre!("Invalid Route 53 endpoint"))?;
+        let host = match url.port() {
+            Some(port) => format!("{host}:{port}"),
+            None => host.to_owned(),
+        };
+
+        let headers = sign(
+            &Request {
+                method: method.as_str(),
+                host: &host,
+                path: url.path(),
+                query: &query,
+                payload: body.as_bytes(),
+            },
+            &SCOPE,
+            &self.credentials,
+            SystemTime::now(),
+        );
+
+        let mut request = self.client.request(method, url);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        if !body.is_empty() {
+            request = request.header("Content-Type", "text/xml").body(body);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        let text = response.text().await?;
+
+        if status.is_success() {
+            Ok(text)
+        } else {
+            Err(eyre!(
+                "Route 53 {operation} failed: {status}: {}: {}",
+                xml::element_text(&text, "Code").unwrap_or_default(),
+                xml::element_text(&text, "Message").unwrap_or_default()
+            ))
+        }
+    }
+}
+
+/// A domain name as Route 53 returns it, without the trailing dot and with
+/// octal escapes such as `\052` for `*` decoded.
+fn decode_name(name: &str) -> String {
+    let name = name.strip_suffix('.').unwrap_or(name);
+    let mut decoded = String::with_capacity(name.len());
+    let mut rest = name;
+
+    while let Some(index) = rest.find('\\') {
+        decoded.push_str(&rest[..index]);
+        rest = &rest[index + 1..];
+
+        match rest.get(..3).and_then(|o| u8::from_str_radix(o, 8).ok()) {
+            Some(byte) => {
+                decoded.push(char::from(byte));
+                rest = &rest[3..];
+            },
+            None => decoded.push('\\'),
+        }
+    }
+
+    decoded.push_str(rest);
+    decoded
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode_name() {
+        assert_eq!(decode_name("example.com."), "example.com");
+        assert_eq!(decode_name("\\052.example.com."), "*.example.com");
+        assert_eq!(decode_name("a\\.b.example.com"), "a\\.b.example.com");
+    }
+}
@@ -0,0 +1,307 @@
+use std::fmt::Write;
+
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use reqwest::Method;
+
+use super::{ROUTE53_API_VERSION, Route53Client, decode_name};
+use crate::xml;
+
+#[derive(Debug, Clone)]
+pub struct ResourceRecordSet {
+    /// As Route 53 returns it: with the trailing dot and octal escapes.
+    pub name: String,
+    pub record_type: String,
+    /// Identifies sets with a routing policy among those of the same name and
+    /// type.
+    pub set_identifier: Option<String>,
+    pub ttl: Option<u32>,
+    pub values: Vec<String>,
+    /// The DNS name an alias set points to.
+    pub alias_target: Option<String>,
+    /// The set as XML, so it can be sent back with every attribute intact.
+    xml: String,
+}
+
+impl ResourceRecordSet {
+    /// Parses the contents of a `ResourceRecordSet` element.
+    pub fn parse(xml: &str) -> Option<Self> {
+        Some(Self {
+            name: xml::element_text(xml, "Name")?,
+            record_type: xml::element_text(xml, "Type")?,
+            set_identifier: xml::element_text(xml, "SetIdentifier"),
+            ttl: xml::element_text(xml, "TTL").and_then(|t| t.parse().ok()),
+            values: xml::elements(xml, "Value")
+                .map(|v| xml::unescape(v.trim()))
+                .collect(),
+            alias_target: xml::elements(xml, "AliasTarget")
+                .next()
+                .and_then(|a| xml::element_text(a, "DNSName")),
+            xml: xml.to_owned(),
+        })
+    }
+
+    /// The name without the trailing dot and with escapes decoded.
+    #[must_use]
+    pub fn decoded_name(&self) -> String {
+        decode_name(&self.name)
+    }
+
+    /// Points the set to `value` alone, keeping the routing policy, health
+    /// check and every other attribute.
+    pub fn set_value(&mut self, value: &str, ttl: Option<u32>) -> Result<()> {
+        let records = format!(
+            "<ResourceRecord><Value>{}</Value></ResourceRecord>",
+            xml::escape(value)
+        );
+        let mut set =
+            xml::replace_element(&self.xml, "ResourceRecords", &records)
+                .ok_or(eyre!(
+                    "Alias record set '{}' has no value.",
+                    self.name
+                ))?;
+
+        if let Some(ttl) = ttl {
+            set = xml::replace_element(&set, "TTL", &ttl.to_string())
+                .unwrap_or(set);
+            self.ttl = Some(ttl);
+        }
+
+        self.xml = set;
+        self.values = vec![value.to_owned()];
+        Ok(())
+    }
+}
+
+/// The query for the next page of a list.
+type NextPage = Vec<(&'static str, String)>;
+
+/// A page of record sets, with the query for the next page.
+async fn list_page(
+    client: &Route53Client,
+    zone_id: &str,
+    start: &[(&str, &str)],
+    max_items: &str,
+) -> Result<(Vec<ResourceRecordSet>, Option<NextPage>)> {
+    let mut query = vec![("maxitems", max_items)];
+    query.extend_from_slice(start);
+
+    let response = client
+        .send(
+            "list_record_sets",
+            Method::GET,
+            &format!("/hostedzone/{zone_id}/rrset"),
+            &query,
+            String::new(),
+        )
+        .await?;
+
+    Ok(parse_list_page(&response))
+}
+
+/// The record sets of a list response, with the query for the next page.
+fn parse_list_page(
+    response: &str,
+) -> (Vec<ResourceRecordSet>, Option<NextPage>) {
+    let record_sets = xml::elements(response, "ResourceRecordSet")
+        .filter_map(ResourceRecordSet::parse)
+        .collect();
+
+    if xml::element_text(response, "IsTruncated").as_deref() != Some("true") {
+        return (record_sets, None);
+    }
+
+    let next = [
+        ("startrecordname", "NextRecordName"),
+        ("startrecordtype", "NextRecordType"),
+        ("startrecordidentifier", "NextRecordIdentifier"),
+    ]
+    .into_iter()
+    .filter_map(|(key, tag)| Some((key, xml::element_text(response, tag)?)))
+    .collect();
+
+    (record_sets, Some(next))
+}
+
+pub async fn list_record_sets(
+    client: &Route53Client,
+    zone_id: &str,
+) -> Result<Vec<ResourceRecordSet>> {
+    let mut all_record_sets = Vec::new();
+    let mut start: Vec<(&str, String)> = Vec::new();
+
+    loop {
+        let start_query: Vec<_> =
+            start.iter().map(|(k, v)| (*k, v.as_str())).collect();
+        let (record_sets, next) =
+            list_page(client, zone_id, &start_query, "300").await?;
+
+        all_record_sets.extend(record_sets);
+
+        match next {
+            Some(next) => start = next,
+            None => break,
+        }
+    }
+
+    Ok(all_record_sets)
+}
+
+/// Creates or replaces `record_sets` in a single change, returning the ID of
+/// the change.
+pub async fn upsert_record_sets(
+    client: &Route53Client,
+    zone_id: &str,
+    record_sets: &[ResourceRecordSet],
+) -> Result<String> {
+    let response = client
+        .send(
+            "upsert_record_sets",
+            Method::POST,
+            &format!("/hostedzone/{zone_id}/rrset"),
+            &[],
+            upsert_request(record_sets),
+        )
+        .await?;
+
+    change_id(&response)
+}
+
+fn upsert_request(record_sets: &[ResourceRecordSet]) -> String {
+    let changes = record_sets.iter().fold(String::new(), |mut changes, r| {
+        let _ = write!(
+            changes,
+            "<Change><Action>UPSERT</Action><ResourceRecordSet>{}</ResourceRecordSet></Change>",
+            r.xml
+        );
+        changes
+    });
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<ChangeResourceRecordSetsRequest xmlns="https://route53.amazonaws.com/doc/{ROUTE53_API_VERSION}/"><ChangeBatch><Comment>Updated by ryndns</Comment><Changes>{changes}</Changes></ChangeBatch></ChangeResourceRecordSetsRequest>"#
+    )
+}
+
+/// The status of a change: `PENDING`, or `INSYNC` once it has reached every
+/// Route 53 name server.
+pub async fn get_change_status(
+    client: &Route53Client,
+    change_id: &str,
+) -> Result<String> {
+    let response = client
+        .send(
+            "get_change",
+            Method::GET,
+            &format!("/change/{change_id}"),
+            &[],
+            String::new(),
+        )
+        .await?;
+
+    xml::element_text(&response, "Status")
+        .ok_or(eyre!("Route 53 get_change returned no status."))
+}
+
+fn change_id(response: &str) -> Result<String> {
+    let id = xml::element_text(response, "Id")
+        .ok_or(eyre!("Route 53 upsert_record_sets returned no change ID."))?;
+    Ok(id.trim_start_matches("/change/").to_owned())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_set_value() -> Result<()> {
+        let xml = "<Name>\\052.example.com.</Name><Type>A</Type>\
+            <SetIdentifier>home</SetIdentifier><Weight>10</Weight>\
+            <TTL>300</TTL><ResourceRecords>\
+            <ResourceRecord><Value>198.51.100.1</Value></ResourceRecord>\
+            <ResourceRecord><Value>198.51.100.2</Value></ResourceRecord>\
+            </ResourceRecords><HealthCheckId>abc</HealthCheckId>";
+
+        let mut record_set = ResourceRecordSet::parse(xml).unwrap();
+        assert_eq!(record_set.decoded_name(), "*.example.com");
+        assert_eq!(record_set.values, ["198.51.100.1", "198.51.100.2"]);
+
+        record_set.set_value("203.0.113.7", Some(60))?;
+        assert_eq!(
+            record_set.xml,
+            "<Name>\\052.example.com.</Name><Type>A</Type>\
+            <SetIdentifier>home</SetIdentifier><Weight>10</Weight>\
+            <TTL>60</TTL><ResourceRecords>\
+            <ResourceRecord><Value>203.0.113.7</Value></ResourceRecord>\
+            </ResourceRecords><HealthCheckId>abc</HealthCheckId>"
+        );
+
+        let alias = "<Name>example.com.</Name><Type>A</Type><AliasTarget>\
+            <HostedZoneId>Z2</HostedZoneId><DNSName>lb.example.net.</DNSName>\
+            <EvaluateTargetHealth>false</EvaluateTargetHealth></AliasTarget>";
+
+        let mut record_set = ResourceRecordSet::parse(alias).unwrap();
+        assert_eq!(record_set.alias_target.as_deref(), Some("lb.example.net."));
+        assert!(record_set.set_value("203.0.113.7", None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_list_page() {
+        let response = "<ListResourceRecordSetsResponse><ResourceRecordSets>\
+            <ResourceRecordSet><Name>example.com.</Name><Type>A</Type>\
+            <TTL>300</TTL><ResourceRecords><ResourceRecord>\
+            <Value>198.51.100.1</Value></ResourceRecord></ResourceRecords>\
+            </ResourceRecordSet></ResourceRecordSets>\
+            <IsTruncated>true</IsTruncated>\
+            <NextRecordName>www.example.com.</NextRecordName>\
+            <NextRecordType>A</NextRecordType>\
+            <NextRecordIdentifier>home</NextRecordIdentifier>\
+            </ListResourceRecordSetsResponse>";
+
+        let (record_sets, next) = parse_list_page(response);
+        assert_eq!(record_sets.len(), 1);
+        assert_eq!(record_sets[0].values, ["198.51.100.1"]);
+        assert_eq!(next.unwrap(), [
+            ("startrecordname", "www.example.com.".to_owned()),
+            ("startrecordtype", "A".to_owned()),
+            ("startrecordidentifier", "home".to_owned()),
+        ]);
+
+        let (record_sets, next) = parse_list_page(
+            "<ListResourceRecordSetsResponse><ResourceRecordSets>\
+             </ResourceRecordSets><IsTruncated>false</IsTruncated>\
+             </ListResourceRecordSetsResponse>",
+        );
+        assert!(record_sets.is_empty());
+        assert!(next.is_none());
+    }
+
+    #[test]
+    fn test_upsert_request() -> Result<()> {
+        let mut record_set = ResourceRecordSet::parse(
+            "<Name>www.example.com.</Name><Type>A</Type>\
+             <SetIdentifier>home</SetIdentifier><Weight>10</Weight>\
+             <TTL>300</TTL><ResourceRecords><ResourceRecord>\
+             <Value>198.51.100.1</Value></ResourceRecord></ResourceRecords>",
+        )
+        .unwrap();
+        record_set.set_value("203.0.113.7", Some(60))?;
+
+        let request = upsert_request(&[record_set]);
+        assert!(request.starts_with(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <ChangeResourceRecordSetsRequest \
+             xmlns=\"https://route53.amazonaws.com/doc/2013-04-01/\">"
+        ));
+        assert!(request.contains(
+            "<Changes><Change><Action>UPSERT</Action><ResourceRecordSet>\
+             <Name>www.example.com.</Name><Type>A</Type>\
+             <SetIdentifier>home</SetIdentifier><Weight>10</Weight>\
+             <TTL>60</TTL><ResourceRecords><ResourceRecord>\
+             <Value>203.0.113.7</Value></ResourceRecord></ResourceRecords>\
+             </ResourceRecordSet></Change></Changes>"
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+use color_eyre::Result;
+use reqwest::Method;
+
+use super::{Route53Client, decode_name};
+use crate::xml;
+
+#[derive(Debug)]
+pub struct HostedZone {
+    /// Without the `/hostedzone/` prefix.
+    pub id: String,
+    pub name: String,
+    pub private: bool,
+}
+
+fn parse_hosted_zones(response: &str) -> Vec<HostedZone> {
+    xml::elements(response, "HostedZone")
+        .filter_map(|zone| {
+            let id = xml::element_text(zone, "Id")?;
+            Some(HostedZone {
+                id: id.trim_start_matches("/hostedzone/").to_owned(),
+                name: decode_name(&xml::element_text(zone, "Name")?),
+                private: xml::element_text(zone, "PrivateZone").as_deref()
+                    == Some("true"),
+            })
+        })
+        .collect()
+}
+
+pub async fn list_hosted_zones(
+    client: &Route53Client,
+) -> Result<Vec<HostedZone>> {
+    let mut all_zones = Vec::new();
+    let mut marker: Option<String> = None;
+
+    loop {
+        let mut query = vec![("maxitems", "100")];
+        if let Some(marker) = &marker {
+            query.push(("marker", marker.as_str()));
+        }
+
+        let response = client
+            .send(
+                "list_hosted_zones",
+                Method::GET,
+                "/hostedzone",
+                &query,
+                String::new(),
+            )
+            .await?;
+
+        all_zones.extend(parse_hosted_zones(&response));
+
+        let truncated = xml::element_text(&response, "IsTruncated");
+        if truncated.as_deref() != Some("true") {
+            break;
+        }
+        marker = xml::element_text(&response, "NextMarker");
+        if marker.is_none() {
+            break;
+        }
+    }
+
+    Ok(all_zones)
+}
+
+/// The hosted zone named `name`, preferring a public zone over a private zone
+/// of the same name.
+pub async fn find_hosted_zone(
+    client: &Route53Client,
+    name: &str,
+) -> Result<Option<HostedZone>> {
+    let response = client
+        .send(
+            "find_hosted_zone",
+            Method::GET,
+            "/hostedzonesbyname",
+            &[("dnsname", name), ("maxitems", "10")],
+            String::new(),
+        )
+        .await?;
+
+    let mut zones: Vec<_> = parse_hosted_zones(&response)
+        .into_iter()
+        .filter(|z| z.name.eq_ignore_ascii_case(name))
+        .collect();
+    zones.sort_by_key(|z| z.private);
+
+    Ok(zones.into_iter().next())
+}
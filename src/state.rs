@@ -1,16 +1,49 @@
-use std::net::Ipv4Addr;
+use std::net::IpAddr;
 
 use camino::Utf8PathBuf;
 use derive_builder::Builder;
+use indexmap::IndexMap;
 
 use crate::ip_cache::IpCache;
+use crate::ip_family::IpFamily;
 
 #[derive(Debug, Builder)]
 pub struct ApplicationState {
     pub config_path: Utf8PathBuf,
     pub ip_cache: IpCache,
     pub ip_cache_path: Utf8PathBuf,
-    pub public_ip_address: Ipv4Addr,
+    /// The public addresses, by IP source name and family.
+    #[builder(default)]
+    pub public_ip_addresses: IndexMap<(String, IpFamily), IpAddr>,
     pub preview: bool,
     pub force: bool,
 }
+
+impl ApplicationStateBuilder {
+    /// Sets the public address `ip_source` reports for the family
+    /// `ip_address` belongs to.
+    pub fn public_ip_address(
+        &mut self,
+        ip_source: &str,
+        ip_address: IpAddr,
+    ) -> &mut Self {
+        self.public_ip_addresses.get_or_insert_with(IndexMap::new).insert(
+            (ip_source.to_owned(), IpFamily::of(ip_address)),
+            ip_address,
+        );
+        self
+    }
+}
+
+impl ApplicationState {
+    /// The public address of the given family reported by `ip_source`, if
+    /// any.
+    #[must_use]
+    pub fn public_ip_address(
+        &self,
+        ip_source: &str,
+        family: IpFamily,
+    ) -> Option<IpAddr> {
+        self.public_ip_addresses.get(&(ip_source.to_owned(), family)).copied()
+    }
+}
@@ -0,0 +1,58 @@
+//! Just enough XML handling to read simple, well-known API responses without
+//! pulling in a full parser. Namespace prefixes and attributes on the
+//! requested elements are not supported.
+
+/// The contents of every `<tag>` element in `xml`, in document order.
+pub(crate) fn elements<'x>(
+    xml: &'x str,
+    tag: &str,
+) -> impl Iterator<Item = &'x str> {
+    let open = format!("<{tag}>");
+    let close = format!("</{tag}>");
+    let mut rest = xml;
+
+    std::iter::from_fn(move || {
+        let start = rest.find(&open)? + open.len();
+        let length = rest[start..].find(&close)?;
+        let contents = &rest[start..start + length];
+        rest = &rest[start + length + close.len()..];
+        Some(contents)
+    })
+}
+
+/// The unescaped text of the first `<tag>` element in `xml`.
+pub(crate) fn element_text(xml: &str, tag: &str) -> Option<String> {
+    elements(xml, tag).next().map(|t| unescape(t.trim()))
+}
+
+pub(crate) fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// `xml` with the contents of its first `<tag>` element replaced by
+/// `contents`, or `None` if there is no such element.
+pub(crate) fn replace_element(
+    xml: &str,
+    tag: &str,
+    contents: &str,
+) -> Option<String> {
+    let open = format!("<{tag}>");
+    let close = format!("</{tag}>");
+
+    let start = xml.find(&open)? + open.len();
+    let end = start + xml[start..].find(&close)?;
+
+    Some(format!("{}{contents}{}", &xml[..start], &xml[end..]))
+}